@@ -0,0 +1,171 @@
+use std::{fs::{self, File}, io::{self, Write}, path::Path};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use tempfile::TempDir;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::{cache, error::LoadError, utils, PACKAGE_FILE_NAME};
+
+/// A single file package, with the same layout as a package directory and the package config at its root
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat
+{
+    Zip,
+    TarGz,
+}
+
+impl ArchiveFormat
+{
+    /// The format of an archive from its extension: `.zip`, `.tar.gz` or `.tgz`
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self>
+    {
+        let name = path.as_ref().file_name()?.to_str()?.to_lowercase();
+        if name.ends_with(".zip")
+        {
+            Some(Self::Zip)
+        }
+        else if name.ends_with(".tar.gz") || name.ends_with(".tgz")
+        {
+            Some(Self::TarGz)
+        }
+        else
+        {
+            None
+        }
+    }
+}
+
+/// Packs every file in the package directory `dir_path`, other than binary caches, into an archive at `archive_path`, in the format given by its extension
+pub fn pack(dir_path: impl AsRef<Path>, archive_path: impl AsRef<Path>) -> Result<(), LoadError>
+{
+    let dir_path = dir_path.as_ref();
+    let archive_path = archive_path.as_ref();
+
+    let Some(format) = ArchiveFormat::from_path(archive_path) else {
+        return Err(LoadError::config(archive_path.display(), None, "Package archives must end in `.zip`, `.tar.gz` or `.tgz`"));
+    };
+
+    if !dir_path.join(PACKAGE_FILE_NAME).is_file()
+    {
+        return Err(LoadError::config(dir_path.display(), None, format!("Expected a package directory containing {}", PACKAGE_FILE_NAME)));
+    }
+
+    let files = utils::package_files(dir_path)?.into_iter().filter(|f| !cache::is_cache_file(f)).collect::<Vec<_>>();
+
+    if let Some(parent) = archive_path.parent()
+    {
+        fs::create_dir_all(parent).map_err(|e| LoadError::io(parent.display(), &e))?;
+    }
+
+    let archive_error = |e: &dyn std::fmt::Display| LoadError::write(archive_path.display(), e);
+
+    let file = File::create(archive_path).map_err(|e| LoadError::io(archive_path.display(), &e))?;
+    match format
+    {
+        ArchiveFormat::Zip => {
+            let mut writer = ZipWriter::new(file);
+            let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+            for name in files.iter()
+            {
+                let data = fs::read(dir_path.join(name)).map_err(|e| LoadError::io(dir_path.join(name).display(), &e))?;
+                writer.start_file(utils::slash_path(name), options).map_err(|e| archive_error(&e))?;
+                writer.write_all(&data).map_err(|e| archive_error(&e))?;
+            }
+
+            writer.finish().map_err(|e| archive_error(&e))?;
+        },
+        ArchiveFormat::TarGz => {
+            let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+            for name in files.iter()
+            {
+                builder.append_path_with_name(dir_path.join(name), utils::slash_path(name)).map_err(|e| archive_error(&e))?;
+            }
+
+            builder.into_inner()
+                .and_then(|encoder| encoder.finish())
+                .map_err(|e| archive_error(&e))?;
+        },
+    }
+
+    Ok(())
+}
+
+/// A package archive extracted into a temporary directory, which is removed when dropped
+#[derive(Debug)]
+pub(crate) struct ExtractedArchive
+{
+    pub dir: TempDir,
+    pub archive_path: String,
+}
+
+impl ExtractedArchive
+{
+    pub fn root(&self) -> String
+    {
+        self.dir.path().display().to_string()
+    }
+
+    /// Points errors into the archive rather than at the temporary directory, which may be gone by the time they are reported
+    pub fn restore_paths(&self, mut errors: Vec<LoadError>) -> Vec<LoadError>
+    {
+        let root = self.root();
+        errors.iter_mut().for_each(|e| e.replace_path_prefix(&root, &self.archive_path));
+        errors
+    }
+}
+
+/// Extracts a package archive into a temporary directory
+pub(crate) fn extract(archive_path: &Path, format: ArchiveFormat) -> Result<ExtractedArchive, LoadError>
+{
+    let dir = tempfile::tempdir().map_err(|e| LoadError::io(archive_path.display(), &e))?;
+    let file = File::open(archive_path).map_err(|e| LoadError::io(archive_path.display(), &e))?;
+
+    let archive_error = |e: &dyn std::fmt::Display| LoadError::Io {
+        path: archive_path.display().to_string(),
+        kind: io::ErrorKind::InvalidData,
+        message: e.to_string()
+    };
+
+    match format
+    {
+        ArchiveFormat::Zip => {
+            let mut archive = ZipArchive::new(file).map_err(|e| archive_error(&e))?;
+            for i in 0..archive.len()
+            {
+                let mut entry = archive.by_index(i).map_err(|e| archive_error(&e))?;
+
+                // entries that would be written outside of the directory are rejected rather than skipped
+                let Some(name) = entry.enclosed_name() else {
+                    return Err(archive_error(&format!("entry {} has an unsafe path", String::from_utf8_lossy(entry.name_raw()))));
+                };
+
+                let path = dir.path().join(name);
+                if entry.is_dir()
+                {
+                    fs::create_dir_all(&path).map_err(|e| archive_error(&e))?;
+                    continue;
+                }
+
+                if let Some(parent) = path.parent()
+                {
+                    fs::create_dir_all(parent).map_err(|e| archive_error(&e))?;
+                }
+
+                let mut out = File::create(&path).map_err(|e| archive_error(&e))?;
+                io::copy(&mut entry, &mut out).map_err(|e| archive_error(&e))?;
+            }
+        },
+        ArchiveFormat::TarGz => {
+            tar::Archive::new(GzDecoder::new(file)).unpack(dir.path()).map_err(|e| archive_error(&e))?;
+        },
+    }
+
+    if !dir.path().join(PACKAGE_FILE_NAME).is_file()
+    {
+        return Err(LoadError::config(archive_path.display(), None, format!("Expected {} at the root of the package archive", PACKAGE_FILE_NAME)));
+    }
+
+    Ok(ExtractedArchive { dir, archive_path: archive_path.display().to_string() })
+}
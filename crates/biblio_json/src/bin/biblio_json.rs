@@ -0,0 +1,38 @@
+use std::process::ExitCode;
+
+use biblio_json::{archive, diagnostics::{Diagnostic, DiagnosticRenderer}, error::LoadError, manifest};
+
+const USAGE: &str = "usage:
+    biblio_json manifest <package dir>          writes the package manifest, listing the size and SHA-256 of every file
+    biblio_json pack <package dir> <archive>    packs the package into a `.zip`, `.tar.gz` or `.tgz` archive";
+
+fn main() -> ExitCode
+{
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(|a| a.as_str()).collect::<Vec<_>>();
+
+    let result = match args.as_slice()
+    {
+        ["manifest", dir] => manifest::write_manifest(dir).map(|m| println!("Wrote a manifest of {} files", m.files.len())),
+        ["pack", dir, archive_path] => archive::pack(dir, archive_path).map(|()| println!("Packed {} into {}", dir, archive_path)),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match result
+    {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            report(&e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn report(error: &LoadError)
+{
+    let diagnostic = Diagnostic::from(error);
+    eprintln!("{}", DiagnosticRenderer::for_terminal(&std::io::stderr()).render_all(&[diagnostic]));
+}
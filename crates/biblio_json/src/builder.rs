@@ -0,0 +1,453 @@
+use std::{collections::{BTreeMap, HashMap}, sync::Arc};
+
+use semver::Version;
+
+use crate::{
+    dependencies::{Dependency, DependencySpec},
+    duplicate_ids,
+    error::LoadError,
+    lazy::PackageModule,
+    lints::LintLevel,
+    modules::{bible::{BibleConfig, BibleModule, BibleSource, Verse, Word}, dict::{DictConfig, DictEntry, DictModule}, lexicon::LexiconModule, xrefs::{MutualRef, XRef, XRefModule, XRefTarget, XRefsConfig}, validate_id, Module},
+    ref_id::RefId,
+    utils,
+    LoadOptions,
+    Package
+};
+
+/// Setters shared by the module builders
+pub trait ModuleBuilder : Sized
+{
+    fn id_mut(&mut self) -> &mut Option<String>;
+
+    /// Defaults to the name, in lowercase with any punctuation or spaces replaced by `_`.
+    /// Ids that cannot be used as a file name, whether set or derived from the name, are reported by `build`
+    fn id(mut self, id: impl Into<String>) -> Self
+    {
+        *self.id_mut() = Some(id.into());
+        self
+    }
+}
+
+/// Builds a [`BibleModule`] in memory. Verses must be added in order, and are checked the same way as when loading from a file
+#[derive(Debug)]
+pub struct BibleBuilder
+{
+    config: BibleConfig,
+    verses: Vec<Verse>,
+}
+
+impl ModuleBuilder for BibleBuilder
+{
+    fn id_mut(&mut self) -> &mut Option<String>
+    {
+        &mut self.config.id
+    }
+}
+
+impl BibleBuilder
+{
+    pub fn new(name: impl Into<String>, language: impl Into<String>) -> Self
+    {
+        Self {
+            config: BibleConfig {
+                id: None,
+                name: name.into(),
+                version: None,
+                description: String::new(),
+                language: language.into(),
+                pub_year: None,
+                data_source: None,
+                books: HashMap::new(),
+            },
+            verses: vec![],
+        }
+    }
+
+    pub fn version(mut self, version: Version) -> Self
+    {
+        self.config.version = Some(version);
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self
+    {
+        self.config.description = description.into();
+        self
+    }
+
+    pub fn pub_year(mut self, pub_year: u32) -> Self
+    {
+        self.config.pub_year = Some(pub_year);
+        self
+    }
+
+    pub fn data_source(mut self, data_source: impl Into<String>) -> Self
+    {
+        self.config.data_source = Some(data_source.into());
+        self
+    }
+
+    /// Sets the full name of a book, e.g. `book("Gen", "Genesis")`
+    pub fn book(mut self, osis_id: impl Into<String>, name: impl Into<String>) -> Self
+    {
+        self.config.books.insert(osis_id.into(), name.into());
+        self
+    }
+
+    pub fn verse(mut self, verse: Verse) -> Self
+    {
+        self.verses.push(verse);
+        self
+    }
+
+    /// Adds a verse made of plain words, with no punctuation or formatting
+    pub fn verse_text(self, id: RefId, text: &str) -> Self
+    {
+        let words = text.split_whitespace().map(|w| Word {
+            red: None,
+            italics: None,
+            begin_punc: None,
+            end_punc: None,
+            text: w.to_owned()
+        }).collect();
+
+        self.verse(Verse { id, words })
+    }
+
+    /// Checks the order of the verses and that every book has a name, reporting every problem found.
+    /// Errors use the Bible name as their path, and the 1 based position of the verse as their line.
+    pub fn build(self) -> Result<BibleModule, Vec<LoadError>>
+    {
+        check_id(&self.config.name, self.config.id.as_deref())?;
+        let source = BibleSource::from_verses(&self.config.name, self.verses, &self.config.books, &LoadOptions::accumulate())?;
+        Ok(BibleModule::new(self.config, source))
+    }
+}
+
+#[derive(Debug)]
+pub struct DictBuilder
+{
+    config: DictConfig,
+    entries: Vec<DictEntry>,
+}
+
+impl ModuleBuilder for DictBuilder
+{
+    fn id_mut(&mut self) -> &mut Option<String>
+    {
+        &mut self.config.id
+    }
+}
+
+impl DictBuilder
+{
+    pub fn new(name: impl Into<String>, language: impl Into<String>) -> Self
+    {
+        Self {
+            config: DictConfig {
+                id: None,
+                name: name.into(),
+                version: None,
+                authors: vec![],
+                language: language.into(),
+                description: None,
+                data_source: None,
+                pub_year: None,
+                license: None,
+                bible_dep: None,
+                markup: None,
+            },
+            entries: vec![],
+        }
+    }
+
+    pub fn version(mut self, version: Version) -> Self
+    {
+        self.config.version = Some(version);
+        self
+    }
+
+    pub fn author(mut self, author: impl Into<String>) -> Self
+    {
+        self.config.authors.push(author.into());
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self
+    {
+        self.config.description = Some(description.into());
+        self
+    }
+
+    pub fn data_source(mut self, data_source: impl Into<String>) -> Self
+    {
+        self.config.data_source = Some(data_source.into());
+        self
+    }
+
+    pub fn pub_year(mut self, pub_year: u32) -> Self
+    {
+        self.config.pub_year = Some(pub_year);
+        self
+    }
+
+    pub fn license(mut self, license: impl Into<String>) -> Self
+    {
+        self.config.license = Some(license.into());
+        self
+    }
+
+    pub fn bible_dep(mut self, bible_dep: impl Into<String>) -> Self
+    {
+        self.config.bible_dep = Some(bible_dep.into());
+        self
+    }
+
+    /// Marks the definitions as written in the definition markup, rather than plain text
+    pub fn markup(mut self) -> Self
+    {
+        self.config.markup = Some(true);
+        self
+    }
+
+    pub fn entry(mut self, entry: DictEntry) -> Self
+    {
+        self.entries.push(entry);
+        self
+    }
+
+    /// Adds an entry with no aliases or references
+    pub fn definition(self, term: impl Into<String>, definitions: impl IntoIterator<Item = impl Into<String>>) -> Self
+    {
+        self.entry(DictEntry {
+            term: term.into(),
+            aliases: None,
+            definitions: definitions.into_iter().map(|d| d.into()).collect(),
+            refs: None
+        })
+    }
+
+    pub fn build(self) -> Result<DictModule, Vec<LoadError>>
+    {
+        check_id(&self.config.name, self.config.id.as_deref())?;
+        Ok(DictModule::new(self.config, self.entries))
+    }
+}
+
+#[derive(Debug)]
+pub struct XRefBuilder
+{
+    config: XRefsConfig,
+    refs: Vec<XRef>,
+}
+
+impl ModuleBuilder for XRefBuilder
+{
+    fn id_mut(&mut self) -> &mut Option<String>
+    {
+        &mut self.config.id
+    }
+}
+
+impl XRefBuilder
+{
+    pub fn new(name: impl Into<String>) -> Self
+    {
+        Self {
+            config: XRefsConfig {
+                id: None,
+                name: name.into(),
+                version: None,
+                description: None,
+                data_source: None,
+                license: None,
+                language: None,
+                pub_year: None,
+                bible_dep: None,
+                versification: None,
+            },
+            refs: vec![],
+        }
+    }
+
+    pub fn version(mut self, version: Version) -> Self
+    {
+        self.config.version = Some(version);
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self
+    {
+        self.config.description = Some(description.into());
+        self
+    }
+
+    pub fn data_source(mut self, data_source: impl Into<String>) -> Self
+    {
+        self.config.data_source = Some(data_source.into());
+        self
+    }
+
+    pub fn license(mut self, license: impl Into<String>) -> Self
+    {
+        self.config.license = Some(license.into());
+        self
+    }
+
+    pub fn language(mut self, language: impl Into<String>) -> Self
+    {
+        self.config.language = Some(language.into());
+        self
+    }
+
+    pub fn pub_year(mut self, pub_year: u32) -> Self
+    {
+        self.config.pub_year = Some(pub_year);
+        self
+    }
+
+    pub fn bible_dep(mut self, bible_dep: impl Into<String>) -> Self
+    {
+        self.config.bible_dep = Some(bible_dep.into());
+        self
+    }
+
+    pub fn xref(mut self, xref: XRef) -> Self
+    {
+        self.refs.push(xref);
+        self
+    }
+
+    pub fn directed(self, source: RefId, targets: impl IntoIterator<Item = impl Into<XRefTarget>>) -> Self
+    {
+        self.xref(XRef::Directed {
+            source,
+            source_text: None,
+            targets: targets.into_iter().map(|t| t.into()).collect(),
+            note: None
+        })
+    }
+
+    pub fn mutual(self, refs: impl IntoIterator<Item = RefId>) -> Self
+    {
+        self.xref(XRef::Mutual {
+            refs: refs.into_iter().map(|id| MutualRef { id, text: None }).collect(),
+            note: None
+        })
+    }
+
+    pub fn chain(self, topic: impl Into<String>, refs: impl IntoIterator<Item = RefId>) -> Self
+    {
+        self.xref(XRef::Chain {
+            topic: topic.into(),
+            refs: refs.into_iter().collect(),
+            note: None
+        })
+    }
+
+    pub fn build(self) -> Result<XRefModule, Vec<LoadError>>
+    {
+        check_id(&self.config.name, self.config.id.as_deref())?;
+        Ok(XRefModule::new(self.config, self.refs))
+    }
+}
+
+#[derive(Debug)]
+pub struct PackageBuilder
+{
+    package: Package,
+}
+
+impl PackageBuilder
+{
+    pub fn new(name: impl Into<String>, license: impl Into<String>) -> Self
+    {
+        Self {
+            package: Package {
+                name: name.into(),
+                version: None,
+                authors: vec![],
+                license: license.into(),
+                modules: vec![],
+                lints: BTreeMap::new(),
+                dependencies: vec![],
+                unlisted_files: vec![],
+            }
+        }
+    }
+
+    pub fn author(mut self, author: impl Into<String>) -> Self
+    {
+        self.package.authors.push(author.into());
+        self
+    }
+
+    pub fn version(mut self, version: Version) -> Self
+    {
+        self.package.version = Some(version);
+        self
+    }
+
+    pub fn dependency(mut self, name: impl Into<String>, spec: DependencySpec, package: Package) -> Self
+    {
+        self.package.dependencies.push(Dependency { name: name.into(), spec, package: Arc::new(package) });
+        self
+    }
+
+    pub fn lint(mut self, name: impl Into<String>, level: LintLevel) -> Self
+    {
+        self.package.lints.insert(name.into(), level);
+        self
+    }
+
+    pub fn module(mut self, module: Module) -> Self
+    {
+        self.package.modules.push(PackageModule::loaded(module));
+        self
+    }
+
+    pub fn bible(self, bible: BibleModule) -> Self
+    {
+        self.module(Module::Bible(bible))
+    }
+
+    pub fn dictionary(self, dict: DictModule) -> Self
+    {
+        self.module(Module::Dictionary(dict))
+    }
+
+    pub fn xrefs(self, xrefs: XRefModule) -> Self
+    {
+        self.module(Module::XRef(xrefs))
+    }
+
+    pub fn lexicon(self, lexicon: LexiconModule) -> Self
+    {
+        self.module(Module::Lexicon(lexicon))
+    }
+
+    /// Checks that every module id can be used as a file name, as modules built elsewhere may have set any id,
+    /// and that no two modules share an id, as the package could not be loaded again once saved
+    pub fn build(self) -> Result<Package, Vec<LoadError>>
+    {
+        let duplicates = duplicate_ids(self.package.modules.iter().map(|m| m.id.as_str()))
+            .into_iter()
+            .map(|id| format!("Module id {} is used by more than one module", id));
+
+        let errors = self.package.modules.iter()
+            .filter_map(|m| validate_id(&m.id).err())
+            .chain(duplicates)
+            .map(|e| LoadError::config(&self.package.name, None, e))
+            .collect::<Vec<_>>();
+
+        if errors.is_empty() { Ok(self.package) } else { Err(errors) }
+    }
+}
+
+/// Checks the id the module will have, which is derived from its name when not set
+fn check_id(name: &str, id: Option<&str>) -> Result<(), Vec<LoadError>>
+{
+    let id = id.map_or_else(|| utils::file_stem(name), |id| id.to_owned());
+    validate_id(&id).map_err(|e| vec![LoadError::config(name, None, e)])
+}
@@ -0,0 +1,146 @@
+use std::{fs::{self, File}, io::{self, Write}, path::{Path, PathBuf}, time::UNIX_EPOCH};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{error::LoadError, LoadOptions};
+
+pub const CACHE_EXTENSION: &str = "cache";
+
+const MAGIC: &[u8; 4] = b"BJSC";
+/// Bumped whenever the layout of the cache, or of any cached module, changes
+const FORMAT_VERSION: u16 = 3;
+
+/// The cache file for a json lines file, e.g. `bibles/kjv.jsonl.cache`
+pub fn cache_path(path: impl AsRef<Path>) -> PathBuf
+{
+    let mut name = path.as_ref().as_os_str().to_owned();
+    name.push(".");
+    name.push(CACHE_EXTENSION);
+    PathBuf::from(name)
+}
+
+pub fn is_cache_file(path: impl AsRef<Path>) -> bool
+{
+    path.as_ref().extension().is_some_and(|e| e == CACHE_EXTENSION)
+}
+
+#[derive(Serialize, Deserialize)]
+struct Header
+{
+    /// The size and modification time of each source file, so an unchanged cache is found without hashing the files
+    stamps: Vec<Stamp>,
+    /// SHA-256 of the contents of every source file, in order
+    hash: [u8; 32],
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq)]
+struct Stamp
+{
+    size: u64,
+    modified_secs: u64,
+    modified_nanos: u32,
+}
+
+/// Loads a module with `load`, unless [`LoadOptions::cache`] is set and the cache next to `data_path` was made from the same config and data files,
+/// in which case the finished module, indexes included, is read from the cache. Modules that fail to load are not cached, so their errors are
+/// reported again on the next load. Problems with the cache itself are never reported, the module is loaded again instead.
+pub(crate) fn load_cached<M>(config_path: &str, data_path: &str, options: &LoadOptions, load: impl FnOnce() -> Result<M, Vec<LoadError>>) -> Result<M, Vec<LoadError>>
+    where M : Serialize + DeserializeOwned
+{
+    if !options.cache
+    {
+        return load();
+    }
+
+    // a source that cannot be read is reported by `load`
+    let sources = [config_path, data_path];
+    let Some(stamps) = sources.iter().map(|s| stamp(s)).collect::<Option<Vec<_>>>() else {
+        return load();
+    };
+
+    let cache_path = cache_path(data_path);
+    let cached = fs::read(&cache_path).ok();
+    let mut hash = None;
+
+    if let Some((header, data)) = cached.as_deref().and_then(read_header)
+    {
+        // files that were touched but not changed are only hashed once, as the cache is then given their new stamps
+        let touched = header.stamps != stamps;
+        if touched
+        {
+            hash = hash_files(&sources);
+        }
+
+        // a cache that cannot be decoded, such as one that was only partly written, is rebuilt like a stale one
+        if (!touched || hash == Some(header.hash)) && let Ok(module) = rmp_serde::from_slice(data)
+        {
+            if touched
+            {
+                let _ = write_cache(&cache_path, &Header { stamps, hash: header.hash }, data);
+            }
+
+            return Ok(module);
+        }
+    }
+
+    let module = load()?;
+
+    // a cache that cannot be written only costs speed, so failures are ignored
+    if let Some(hash) = hash.or_else(|| hash_files(&sources))
+        && let Ok(data) = rmp_serde::to_vec_named(&module)
+    {
+        let _ = write_cache(&cache_path, &Header { stamps, hash }, &data);
+    }
+
+    Ok(module)
+}
+
+fn stamp(path: &str) -> Option<Stamp>
+{
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some(Stamp { size: metadata.len(), modified_secs: modified.as_secs(), modified_nanos: modified.subsec_nanos() })
+}
+
+fn hash_files(paths: &[&str]) -> Option<[u8; 32]>
+{
+    let mut hasher = Sha256::new();
+    for path in paths
+    {
+        io::copy(&mut File::open(path).ok()?, &mut hasher).ok()?;
+    }
+
+    Some(hasher.finalize().into())
+}
+
+/// The header and the encoded module, or `None` if the file is not a cache from this format version
+fn read_header(bytes: &[u8]) -> Option<(Header, &[u8])>
+{
+    let rest = bytes.strip_prefix(MAGIC)?;
+    let (version, mut rest) = rest.split_first_chunk::<2>()?;
+    if u16::from_le_bytes(*version) != FORMAT_VERSION
+    {
+        return None;
+    }
+
+    let header = rmp_serde::from_read(&mut rest).ok()?;
+    Some((header, rest))
+}
+
+fn write_cache(path: &Path, header: &Header, data: &[u8]) -> Result<(), LoadError>
+{
+    let mut bytes = vec![];
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    rmp_serde::encode::write_named(&mut bytes, header).map_err(|e| LoadError::write(path.display(), e))?;
+    bytes.extend_from_slice(data);
+
+    // written to a temporary file first, so a load running at the same time never reads a partial cache
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut file = tempfile::NamedTempFile::new_in(dir).map_err(|e| LoadError::io(dir.display(), &e))?;
+    file.write_all(&bytes).map_err(|e| LoadError::io(path.display(), &e))?;
+    file.persist(path).map_err(|e| LoadError::io(path.display(), &e.error))?;
+
+    Ok(())
+}
@@ -0,0 +1,296 @@
+use std::{num::NonZeroU32, sync::LazyLock};
+
+use crate::ref_id::{Atom, RefId};
+
+const BOOK_NAMES: &[(&str, &[&str])] = &[
+    ("Gen", &["Genesis", "Gen", "Ge", "Gn"]),
+    ("Exod", &["Exodus", "Exod", "Exo", "Ex"]),
+    ("Lev", &["Leviticus", "Lev", "Le", "Lv"]),
+    ("Num", &["Numbers", "Num", "Nu", "Nm", "Nb"]),
+    ("Deut", &["Deuteronomy", "Deut", "Deu", "Dt"]),
+    ("Josh", &["Joshua", "Josh", "Jos", "Jsh"]),
+    ("Judg", &["Judges", "Judg", "Jdg", "Jg", "Jdgs"]),
+    ("Ruth", &["Ruth", "Rth", "Ru"]),
+    ("1Sam", &["1 Samuel", "1 Sam", "1 Sa", "1 Sm"]),
+    ("2Sam", &["2 Samuel", "2 Sam", "2 Sa", "2 Sm"]),
+    ("1Kgs", &["1 Kings", "1 Kgs", "1 Kin", "1 Ki"]),
+    ("2Kgs", &["2 Kings", "2 Kgs", "2 Kin", "2 Ki"]),
+    ("1Chr", &["1 Chronicles", "1 Chron", "1 Chr", "1 Ch"]),
+    ("2Chr", &["2 Chronicles", "2 Chron", "2 Chr", "2 Ch"]),
+    ("Ezra", &["Ezra", "Ezr"]),
+    ("Neh", &["Nehemiah", "Neh", "Ne"]),
+    ("Esth", &["Esther", "Esth", "Est", "Es"]),
+    ("Job", &["Job", "Jb"]),
+    ("Ps", &["Psalms", "Psalm", "Psa", "Pss", "Ps"]),
+    ("Prov", &["Proverbs", "Prov", "Pro", "Prv", "Pr"]),
+    ("Eccl", &["Ecclesiastes", "Eccles", "Eccl", "Ecc", "Ec", "Qoh"]),
+    ("Song", &["Song of Solomon", "Song of Songs", "Song", "Cant", "SS"]),
+    ("Isa", &["Isaiah", "Isa"]),
+    ("Jer", &["Jeremiah", "Jer", "Je", "Jr"]),
+    ("Lam", &["Lamentations", "Lam", "La"]),
+    ("Ezek", &["Ezekiel", "Ezek", "Eze", "Ezk"]),
+    ("Dan", &["Daniel", "Dan", "Da", "Dn"]),
+    ("Hos", &["Hosea", "Hos", "Ho"]),
+    ("Joel", &["Joel", "Jl"]),
+    ("Amos", &["Amos", "Am"]),
+    ("Obad", &["Obadiah", "Obad", "Ob"]),
+    ("Jonah", &["Jonah", "Jon", "Jnh"]),
+    ("Mic", &["Micah", "Mic", "Mc"]),
+    ("Nah", &["Nahum", "Nah", "Na"]),
+    ("Hab", &["Habakkuk", "Hab", "Hb"]),
+    ("Zeph", &["Zephaniah", "Zeph", "Zep", "Zp"]),
+    ("Hag", &["Haggai", "Hag", "Hg"]),
+    ("Zech", &["Zechariah", "Zech", "Zec", "Zc"]),
+    ("Mal", &["Malachi", "Mal", "Ml"]),
+    ("Matt", &["Matthew", "Matt", "Mat", "Mt"]),
+    ("Mark", &["Mark", "Mrk", "Mar", "Mk"]),
+    ("Luke", &["Luke", "Luk", "Lk"]),
+    ("John", &["John", "Joh", "Jhn", "Jn"]),
+    ("Acts", &["Acts", "Act", "Ac"]),
+    ("Rom", &["Romans", "Rom", "Ro", "Rm"]),
+    ("1Cor", &["1 Corinthians", "1 Cor", "1 Co"]),
+    ("2Cor", &["2 Corinthians", "2 Cor", "2 Co"]),
+    ("Gal", &["Galatians", "Gal", "Ga"]),
+    ("Eph", &["Ephesians", "Eph", "Ephes"]),
+    ("Phil", &["Philippians", "Phil", "Php", "Pp"]),
+    ("Col", &["Colossians", "Col", "Co"]),
+    ("1Thess", &["1 Thessalonians", "1 Thess", "1 Thes", "1 Th"]),
+    ("2Thess", &["2 Thessalonians", "2 Thess", "2 Thes", "2 Th"]),
+    ("1Tim", &["1 Timothy", "1 Tim", "1 Ti"]),
+    ("2Tim", &["2 Timothy", "2 Tim", "2 Ti"]),
+    ("Titus", &["Titus", "Tit", "Ti"]),
+    ("Phlm", &["Philemon", "Philem", "Phlm", "Phm"]),
+    ("Heb", &["Hebrews", "Heb"]),
+    ("Jas", &["James", "Jas", "Jm"]),
+    ("1Pet", &["1 Peter", "1 Pet", "1 Pe", "1 Pt"]),
+    ("2Pet", &["2 Peter", "2 Pet", "2 Pe", "2 Pt"]),
+    ("1John", &["1 John", "1 Jn", "1 Jhn", "1 Joh"]),
+    ("2John", &["2 John", "2 Jn", "2 Jhn", "2 Joh"]),
+    ("3John", &["3 John", "3 Jn", "3 Jhn", "3 Joh"]),
+    ("Jude", &["Jude", "Jud", "Jd"]),
+    ("Rev", &["Revelation", "Rev", "Re", "Rv"]),
+];
+
+/// Short names that are also words or common abbreviations, e.g. `I Am 5 years`, so they are only read as a book when a verse follows
+const AMBIGUOUS_NAMES: &[&str] = &["Am", "Ac", "Co", "Ex", "Ho", "La", "Na", "Pp", "Re", "SS", "Ti"];
+
+static BOOK_NAME_VARIANTS: LazyLock<Vec<(&'static str, String)>> = LazyLock::new(book_name_variants);
+
+/// Finds scripture citations such as `Gen. 14:18`, `1 Kings 3:4-9; 4:1` or `Ps 23`
+/// in free text and converts them into OSIS style [`RefId`]s.
+pub fn extract_refs(text: &str) -> Vec<RefId>
+{
+    let chars = text.chars().collect::<Vec<_>>();
+
+    let mut refs = vec![];
+    let mut i = 0;
+    while i < chars.len()
+    {
+        let at_boundary = i == 0 || !chars[i - 1].is_alphanumeric();
+        let matched = if at_boundary { BOOK_NAME_VARIANTS.iter().find_map(|(osis, name)| match_name(&chars, i, name).map(|end| (*osis, name, end))) } else { None };
+
+        let Some((osis, name, name_end)) = matched else {
+            i += 1;
+            continue;
+        };
+
+        let has_verse = parse_number(&chars, name_end).is_some_and(|(_, end)| chars.get(end) == Some(&':'));
+        if !has_verse && AMBIGUOUS_NAMES.contains(&name.as_str())
+        {
+            i = name_end;
+            continue;
+        }
+
+        match parse_passages(&chars, name_end, osis)
+        {
+            Some((passages, end)) => {
+                refs.extend(passages);
+                i = end;
+            },
+            None => i = name_end,
+        }
+    }
+
+    refs
+}
+
+/// The canonical position of an OSIS book id, e.g. `Gen` is 0 and `Rev` is 65
+pub fn book_order(osis: &str) -> Option<usize>
+{
+    BOOK_NAMES.iter().position(|(id, _)| *id == osis)
+}
+
+/// The OSIS ids of the books from `from` to `to` inclusive, in canonical order, or `None` if either is not a known book or `to` comes first
+pub(crate) fn books_between(from: &str, to: &str) -> Option<impl Iterator<Item = &'static str>>
+{
+    let (from, to) = (book_order(from)?, book_order(to)?);
+    (from <= to).then(|| BOOK_NAMES[from..=to].iter().map(|(id, _)| *id))
+}
+
+fn book_name_variants() -> Vec<(&'static str, String)>
+{
+    let mut names = vec![];
+    for (osis, book_names) in BOOK_NAMES
+    {
+        for name in book_names.iter()
+        {
+            names.push((*osis, name.to_string()));
+
+            let Some((number, rest)) = name.split_once(' ') else {
+                continue;
+            };
+
+            let Ok(number) = number.parse::<usize>() else {
+                continue;
+            };
+
+            names.push((*osis, format!("{}{}", number, rest)));
+            names.push((*osis, format!("{} {}", "I".repeat(number), rest)));
+        }
+    }
+
+    // longest names first, so `1 John` is preferred over `John`, and `Psalms` over `Ps`
+    names.sort_by_key(|(_, name)| std::cmp::Reverse(name.len()));
+    names
+}
+
+fn match_name(chars: &[char], start: usize, name: &str) -> Option<usize>
+{
+    let mut i = start;
+    for c in name.chars()
+    {
+        if chars.get(i) != Some(&c)
+        {
+            return None;
+        }
+        i += 1;
+    }
+
+    if chars.get(i).is_some_and(|c| c.is_alphanumeric())
+    {
+        return None;
+    }
+
+    if chars.get(i) == Some(&'.')
+    {
+        i += 1;
+    }
+
+    let spaces = skip_spaces(chars, i);
+    if spaces == i || !chars.get(spaces).is_some_and(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+
+    Some(spaces)
+}
+
+fn parse_passages(chars: &[char], start: usize, osis: &str) -> Option<(Vec<RefId>, usize)>
+{
+    let (chapter, mut i) = parse_number(chars, start)?;
+    let mut chapter = chapter;
+    let mut refs = vec![];
+
+    if chars.get(i) != Some(&':')
+    {
+        refs.push(RefId::Single(Atom::Chapter { book: osis.to_owned(), chapter }));
+        return Some((refs, i));
+    }
+
+    let (verse, end) = parse_number(chars, i + 1)?;
+    let (id, end) = parse_range_end(chars, end, osis, chapter, verse);
+    refs.push(id);
+    i = end;
+
+    // continuations such as `14:18, 20; 15:1`
+    while let Some(sep) = chars.get(i).copied().filter(|c| *c == ',' || *c == ';')
+    {
+        let next = skip_spaces(chars, i + 1);
+        let Some((number, after_number)) = parse_number(chars, next) else {
+            break;
+        };
+
+        if chars.get(after_number) == Some(&':')
+        {
+            let Some((verse, end)) = parse_number(chars, after_number + 1) else {
+                break;
+            };
+
+            chapter = number;
+            let (id, end) = parse_range_end(chars, end, osis, chapter, verse);
+            refs.push(id);
+            i = end;
+        }
+        else if sep == ',' && !starts_book_name(chars, after_number)
+        {
+            let (id, end) = parse_range_end(chars, after_number, osis, chapter, number);
+            refs.push(id);
+            i = end;
+        }
+        else
+        {
+            break;
+        }
+    }
+
+    Some((refs, i))
+}
+
+fn parse_range_end(chars: &[char], start: usize, osis: &str, chapter: NonZeroU32, verse: NonZeroU32) -> (RefId, usize)
+{
+    let from = Atom::Verse { book: osis.to_owned(), chapter, verse };
+
+    let is_dash = chars.get(start).is_some_and(|c| *c == '-' || *c == '–');
+    let range_end = if is_dash { parse_number(chars, start + 1) } else { None };
+
+    let Some((number, end)) = range_end else {
+        return (RefId::Single(from), start);
+    };
+
+    if chars.get(end) == Some(&':') && let Some((to_verse, end)) = parse_number(chars, end + 1)
+    {
+        let to = Atom::Verse { book: osis.to_owned(), chapter: number, verse: to_verse };
+        return (RefId::Range { from, to }, end);
+    }
+
+    if number <= verse
+    {
+        return (RefId::Single(from), start);
+    }
+
+    let to = Atom::Verse { book: osis.to_owned(), chapter, verse: number };
+    (RefId::Range { from, to }, end)
+}
+
+fn parse_number(chars: &[char], start: usize) -> Option<(NonZeroU32, usize)>
+{
+    let end = chars[start.min(chars.len())..].iter()
+        .position(|c| !c.is_ascii_digit())
+        .map(|p| start + p)
+        .unwrap_or(chars.len());
+
+    if end == start
+    {
+        return None;
+    }
+
+    let number = chars[start..end].iter().collect::<String>().parse().ok()?;
+    Some((number, end))
+}
+
+fn skip_spaces(chars: &[char], start: usize) -> usize
+{
+    let mut i = start;
+    while chars.get(i).is_some_and(|c| *c == ' ' || *c == '\u{a0}')
+    {
+        i += 1;
+    }
+    i
+}
+
+fn starts_book_name(chars: &[char], start: usize) -> bool
+{
+    // `2 Kings` and `1Jn` start a book name, while `5a` is a verse with a letter after it
+    let next = skip_spaces(chars, start);
+    chars.get(next).is_some_and(|c| if next > start { c.is_alphabetic() } else { c.is_uppercase() })
+}
@@ -0,0 +1,220 @@
+use std::{collections::{BTreeMap, HashMap}, fs, path::{Path, PathBuf}, sync::{Arc, Mutex}};
+
+use itertools::Itertools;
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::LoadError, utils, PackageConfig, Package, PACKAGE_FILE_NAME};
+
+/// An entry in the `[dependencies]` table of a package config, either `kjv = "^1.0"` or `kjv = { version = "^1.0", path = "../kjv" }`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum DependencySpec
+{
+    Version(VersionReq),
+    Detailed
+    {
+        version: VersionReq,
+        /// A package directory or archive, relative to the depending package. When not given the dependency is found in the registry
+        path: Option<String>,
+    },
+}
+
+impl DependencySpec
+{
+    pub fn version(&self) -> &VersionReq
+    {
+        match self
+        {
+            Self::Version(version) => version,
+            Self::Detailed { version, path: _ } => version,
+        }
+    }
+
+    pub fn path(&self) -> Option<&str>
+    {
+        match self
+        {
+            Self::Version(_) => None,
+            Self::Detailed { version: _, path } => path.as_deref(),
+        }
+    }
+}
+
+/// A package this package depends on, loaded from the path or registry its [`DependencySpec`] resolved to.
+/// Packages that several others depend on are loaded once and shared.
+#[derive(Debug)]
+pub struct Dependency
+{
+    pub name: String,
+    pub spec: DependencySpec,
+    pub package: Arc<Package>,
+}
+
+type LoadResult = Result<Arc<Package>, Vec<LoadError>>;
+
+/// The packages loaded so far by their canonical path, including the ones that failed to load
+#[derive(Debug, Default)]
+pub(crate) struct LoadedPackages
+{
+    packages: Mutex<HashMap<PathBuf, LoadResult>>,
+}
+
+impl LoadedPackages
+{
+    pub fn get(&self, path: &Path) -> Option<LoadResult>
+    {
+        self.packages.lock().unwrap_or_else(|e| e.into_inner()).get(path).cloned()
+    }
+
+    pub fn insert(&self, path: PathBuf, package: LoadResult)
+    {
+        self.packages.lock().unwrap_or_else(|e| e.into_inner()).insert(path, package);
+    }
+}
+
+/// A directory of packages, each in its own sub directory. Several versions of a package may be present, in which case
+/// the highest version matching a dependency is used.
+#[derive(Debug, Clone)]
+pub struct Registry
+{
+    pub path: PathBuf,
+    entries: Vec<RegistryEntry>,
+}
+
+#[derive(Debug, Clone)]
+struct RegistryEntry
+{
+    name: String,
+    version: Option<Version>,
+    path: PathBuf,
+}
+
+impl Registry
+{
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, LoadError>
+    {
+        let path = path.as_ref();
+        let dirs = fs::read_dir(path).map_err(|e| LoadError::io(path.display(), &e))?;
+
+        let mut package_dirs = vec![];
+        for dir in dirs
+        {
+            let dir = dir.map_err(|e| LoadError::io(path.display(), &e))?.path();
+            if dir.join(PACKAGE_FILE_NAME).is_file()
+            {
+                package_dirs.push(dir);
+            }
+        }
+
+        Self::from_dirs(path, package_dirs)
+    }
+
+    /// A registry over package directories found some other way, such as by a [`crate::library::Library`]
+    pub fn from_dirs(path: impl AsRef<Path>, package_dirs: impl IntoIterator<Item = PathBuf>) -> Result<Self, LoadError>
+    {
+        let mut configs = vec![];
+        for dir in package_dirs
+        {
+            let config: PackageConfig = utils::load_toml(dir.join(PACKAGE_FILE_NAME))?;
+            configs.push((dir, config));
+        }
+
+        Ok(Self::from_configs(path, configs.iter().map(|(dir, config)| (dir.as_path(), config))))
+    }
+
+    pub(crate) fn from_configs<'a>(path: impl AsRef<Path>, configs: impl IntoIterator<Item = (&'a Path, &'a PackageConfig)>) -> Self
+    {
+        let entries = configs.into_iter()
+            .map(|(dir, config)| RegistryEntry { name: config.name.clone(), version: config.version.clone(), path: dir.to_owned() })
+            .collect();
+
+        Self { path: path.as_ref().to_owned(), entries }
+    }
+
+    /// The directory of the highest version of the package named `name` that matches `version`.
+    /// Names are compared ignoring case and punctuation, so `tsk_xrefs` finds a package named `TSK Xrefs`.
+    pub fn find(&self, name: &str, version: &VersionReq) -> Option<&Path>
+    {
+        let name = utils::file_stem(name);
+        self.entries.iter()
+            .filter(|e| utils::file_stem(&e.name) == name)
+            .filter_map(|e| e.version.as_ref().filter(|v| version.matches(v)).map(|v| (v, &e.path)))
+            .max_by(|(a, _), (b, _)| a.cmp(b))
+            .map(|(_, path)| path.as_path())
+    }
+}
+
+/// Loads each dependency of the package at `dir_path`, checking their versions. Dependency paths are relative to `base_dir`,
+/// which is the directory containing the archive for archived packages. `stack` holds the canonical paths of the packages
+/// currently being loaded, so that circular dependencies are reported rather than loaded forever.
+pub(crate) fn load_dependencies(
+    dir_path: &str,
+    base_dir: &Path,
+    specs: &BTreeMap<String, DependencySpec>,
+    registry: Option<&Registry>,
+    stack: &mut Vec<PathBuf>,
+    load: impl Fn(&str, &mut Vec<PathBuf>) -> Result<Arc<Package>, Vec<LoadError>>
+) -> Result<Vec<Dependency>, Vec<LoadError>>
+{
+    let mut dependencies = vec![];
+    let mut errors = vec![];
+    let config_path = Path::new(dir_path).join(PACKAGE_FILE_NAME).display().to_string();
+
+    for (name, spec) in specs.iter()
+    {
+        let path = match resolve(name, spec, base_dir, registry) {
+            Ok(ok) => ok,
+            Err(message) => {
+                errors.push(LoadError::config(&config_path, None, message));
+                continue;
+            }
+        };
+
+        if let Ok(canonical) = path.canonicalize() && stack.contains(&canonical)
+        {
+            let chain = stack.iter().chain([&canonical]).map(|p| p.display()).join(" -> ");
+            errors.push(LoadError::config(&config_path, None, format!("Circular dependency on {} ({})", name, chain)));
+            continue;
+        }
+
+        let package = match load(&path.display().to_string(), stack) {
+            Ok(ok) => ok,
+            Err(e) => {
+                errors.extend(e);
+                continue;
+            }
+        };
+
+        match &package.version
+        {
+            Some(version) if spec.version().matches(version) => {},
+            Some(version) => {
+                errors.push(LoadError::config(&config_path, None, format!("Dependency {} requires version {}, but {} is version {}", name, spec.version(), path.display(), version)));
+                continue;
+            },
+            None => {
+                errors.push(LoadError::config(&config_path, None, format!("Dependency {} requires version {}, but {} has no version", name, spec.version(), path.display())));
+                continue;
+            }
+        }
+
+        dependencies.push(Dependency { name: name.clone(), spec: spec.clone(), package });
+    }
+
+    if errors.is_empty() { Ok(dependencies) } else { Err(errors) }
+}
+
+/// The path a dependency points to, from its `path` or else the registry
+pub(crate) fn resolve(name: &str, spec: &DependencySpec, base_dir: &Path, registry: Option<&Registry>) -> Result<PathBuf, String>
+{
+    match (spec.path(), registry)
+    {
+        (Some(path), _) => Ok(base_dir.join(path)),
+        (None, Some(registry)) => match registry.find(name, spec.version()) {
+            Some(path) => Ok(path.to_owned()),
+            None => Err(format!("No package named {} matching version {} in the registry {}", name, spec.version(), registry.path.display())),
+        },
+        (None, None) => Err(format!("Dependency {} has no `path` and no registry was given", name)),
+    }
+}
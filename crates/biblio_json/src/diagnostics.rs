@@ -0,0 +1,352 @@
+use std::{collections::HashMap, fmt::Display, io::IsTerminal};
+
+use crate::{error::{LoadError, OrderingError}, lints::{LintFinding, LintLevel}, utils, PackageValidationError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity
+{
+    Warning,
+    Error,
+}
+
+impl Display for Severity
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        match self
+        {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A load or validation problem, with enough information to point at the offending source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic
+{
+    pub severity: Severity,
+    pub message: String,
+    pub path: Option<String>,
+    /// 1 based
+    pub line: Option<usize>,
+    /// 1 based, in characters
+    pub column: Option<usize>,
+    /// Text on the line to underline when there is no column, e.g. the id of an out of order verse
+    pub highlight: Option<String>,
+    pub hint: Option<String>,
+}
+
+impl Diagnostic
+{
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self
+    {
+        Self { severity, message: message.into(), path: None, line: None, column: None, highlight: None, hint: None }
+    }
+
+    /// An empty path, as modules built in memory have, is left out
+    pub fn at(self, path: impl Into<String>, line: Option<usize>, column: Option<usize>) -> Self
+    {
+        let path = Some(path.into()).filter(|p| !p.is_empty());
+        Self { path, line, column, ..self }
+    }
+
+    pub fn highlight(self, highlight: impl Into<String>) -> Self
+    {
+        Self { highlight: Some(highlight.into()), ..self }
+    }
+
+    pub fn hint(self, hint: impl Into<String>) -> Self
+    {
+        Self { hint: Some(hint.into()), ..self }
+    }
+}
+
+impl From<&LoadError> for Diagnostic
+{
+    fn from(error: &LoadError) -> Self
+    {
+        match error
+        {
+            LoadError::Io { path, kind: _, message } => {
+                Diagnostic::new(Severity::Error, format!("could not access file: {}", message))
+                    .at(path, None, None)
+            },
+            LoadError::Toml { path, line, column, message } => {
+                Diagnostic::new(Severity::Error, format!("invalid toml: {}", message))
+                    .at(path, *line, *column)
+            },
+            LoadError::JsonLine { path, line, column, message } => {
+                // serde_json reports the position again at the end of the message, which is redundant here
+                let message = message.split(" at line ").next().unwrap_or(message);
+                Diagnostic::new(Severity::Error, format!("invalid json: {}", message))
+                    .at(path, Some(*line), Some(*column))
+            },
+            LoadError::Ordering { path, line, error } => ordering_diagnostic(error).at(path, Some(*line), None),
+            LoadError::Config { path, line, message } => {
+                Diagnostic::new(Severity::Error, message.clone())
+                    .at(path, *line, None)
+            },
+            LoadError::Integrity { path, error: _ } => {
+                Diagnostic::new(Severity::Error, error.to_string())
+                    .at(path, None, None)
+                    .hint("the file may be corrupted or modified; if the change was intended, regenerate the manifest")
+            },
+            LoadError::Glob { pattern, message } => {
+                Diagnostic::new(Severity::Error, format!("invalid module path `{}`: {}", pattern, message))
+            },
+            LoadError::Write { path, message } => {
+                Diagnostic::new(Severity::Error, format!("could not write file: {}", message))
+                    .at(path, None, None)
+            },
+        }
+    }
+}
+
+fn ordering_diagnostic(error: &OrderingError) -> Diagnostic
+{
+    match error
+    {
+        OrderingError::InvalidId { id } => {
+            Diagnostic::new(Severity::Error, format!("verse id {} is not in the format `book.chapter.verse`", id))
+                .highlight(id.to_string())
+                .hint("each line must be a single verse, like `Gen.1.1`")
+        },
+        OrderingError::Book { book } => {
+            Diagnostic::new(Severity::Error, format!("book {} has already been defined and is out of order", book))
+                .highlight(book)
+                .hint(format!("all verses of {} must be on consecutive lines", book))
+        },
+        OrderingError::Chapter { id, expected } => {
+            let (_, chapter, _) = id.get_verse_components().unwrap_or_default();
+            Diagnostic::new(Severity::Error, format!("verse {} has a chapter number that is out of order", id))
+                .highlight(id.to_string())
+                .hint(format!("chapter {} follows chapter {}; expected {}", chapter, expected - 1, expected))
+        },
+        OrderingError::Verse { id, expected } => {
+            let (_, _, verse) = id.get_verse_components().unwrap_or_default();
+            Diagnostic::new(Severity::Error, format!("verse {} has a verse number that is out of order", id))
+                .highlight(id.to_string())
+                .hint(format!("verse {} follows verse {}; expected {}", verse, expected - 1, expected))
+        },
+    }
+}
+
+impl From<&PackageValidationError> for Diagnostic
+{
+    fn from(error: &PackageValidationError) -> Self
+    {
+        let diagnostic = Diagnostic::new(error.severity(), error.to_string());
+        match error
+        {
+            PackageValidationError::MissingBibleDep { xref_name: _ } |
+            PackageValidationError::MissingDictBibleDep { dict_name: _ } => {
+                diagnostic.hint("add `bible_dep = \"<bible id>\"` to the module config")
+            },
+            PackageValidationError::ModuleLoad { id: _, error } => {
+                diagnostic.at(error.path(), error.line(), None)
+            },
+            PackageValidationError::InvalidChain { xref_name: _, path, topic, line, reason: _ } => {
+                diagnostic.at(path, Some(*line), None).highlight(topic)
+            },
+            PackageValidationError::InvalidRefId { id, bible_name: _, xref_name: _, path, line } |
+            PackageValidationError::InvalidDictRefId { id, bible_name: _, dict_name: _, path, term: _, line } => {
+                diagnostic.at(path, Some(*line), None).highlight(id.to_string())
+            },
+            PackageValidationError::InvalidMarkup { dict_name: _, path, term: _, line, error: _ } => {
+                diagnostic.at(path, Some(*line), None)
+            },
+            PackageValidationError::UnresolvedEntryLink { dict_name: _, path, term: _, target, line } => {
+                diagnostic.at(path, Some(*line), None).highlight(target)
+            },
+            PackageValidationError::DuplicateDictTerm { dict_name: _, path, term, line, first_line } => {
+                diagnostic.at(path, Some(*line), None)
+                    .highlight(term)
+                    .hint(format!("merge the definitions into the entry on line {}", first_line))
+            },
+            PackageValidationError::DictAliasCollision { dict_name: _, path, term: _, alias, other_term: _, line } => {
+                diagnostic.at(path, Some(*line), None).highlight(alias)
+            },
+            PackageValidationError::EmptyDefinition { dict_name: _, path, term, line } => {
+                diagnostic.at(path, Some(*line), None).highlight(term)
+            },
+            PackageValidationError::DictHeaderRow { dict_name: _, path, term, line } => {
+                diagnostic.at(path, Some(*line), None)
+                    .highlight(term)
+                    .hint("remove the header row left over from the spreadsheet export")
+            },
+            PackageValidationError::DictWhitespace { dict_name: _, path, term: _, text, line } => {
+                diagnostic.at(path, Some(*line), None).highlight(text)
+            },
+            _ => diagnostic,
+        }
+    }
+}
+
+impl From<&LintFinding> for Diagnostic
+{
+    fn from(finding: &LintFinding) -> Self
+    {
+        let severity = match finding.level
+        {
+            LintLevel::Deny => Severity::Error,
+            _ => Severity::Warning,
+        };
+
+        Diagnostic::new(severity, format!("{} [{}]", finding.message, finding.lint))
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+
+/// Renders diagnostics in a compiler like format, with the offending source line when it can be read
+#[derive(Debug, Default)]
+pub struct DiagnosticRenderer
+{
+    pub color: bool,
+    sources: HashMap<String, Option<String>>,
+}
+
+impl DiagnosticRenderer
+{
+    pub fn plain() -> Self
+    {
+        Self { color: false, sources: HashMap::new() }
+    }
+
+    pub fn colored() -> Self
+    {
+        Self { color: true, sources: HashMap::new() }
+    }
+
+    /// Uses color if `stream`, the stdout or stderr the diagnostics are printed to, is a terminal
+    pub fn for_terminal(stream: &impl IsTerminal) -> Self
+    {
+        Self { color: stream.is_terminal(), sources: HashMap::new() }
+    }
+
+    pub fn render(&mut self, diagnostic: &Diagnostic) -> String
+    {
+        let mut out = String::new();
+        let severity_color = match diagnostic.severity
+        {
+            Severity::Warning => YELLOW,
+            Severity::Error => RED,
+        };
+
+        out.push_str(&format!("{}{}{}: {}{}{}\n", self.paint(severity_color), diagnostic.severity, self.paint(RESET), self.paint(BOLD), diagnostic.message, self.paint(RESET)));
+
+        let source_line = match (&diagnostic.path, diagnostic.line)
+        {
+            (Some(path), Some(line)) => self.source_line(path, line),
+            _ => None,
+        };
+
+        let gutter = diagnostic.line.map_or(0, |l| l.to_string().len());
+        let padding = " ".repeat(gutter);
+
+        if let Some(path) = &diagnostic.path
+        {
+            let location = match (diagnostic.line, diagnostic.column)
+            {
+                (Some(line), Some(column)) => format!("{}:{}:{}", path, line, column),
+                (Some(line), None) => format!("{}:{}", path, line),
+                _ => path.clone(),
+            };
+
+            out.push_str(&format!("{}{}-->{} {}\n", padding, self.paint(BLUE), self.paint(RESET), location));
+        }
+
+        if let (Some(text), Some(line)) = (&source_line, diagnostic.line)
+        {
+            out.push_str(&format!("{} {}|{}\n", padding, self.paint(BLUE), self.paint(RESET)));
+            out.push_str(&format!("{}{} |{} {}\n", self.paint(BLUE), line, self.paint(RESET), text));
+
+            if let Some((start, len)) = underline(text, diagnostic)
+            {
+                out.push_str(&format!(
+                    "{} {}|{} {}{}{}{}\n",
+                    padding, self.paint(BLUE), self.paint(RESET), " ".repeat(start), self.paint(severity_color), "^".repeat(len), self.paint(RESET)
+                ));
+            }
+        }
+
+        if let Some(hint) = &diagnostic.hint
+        {
+            out.push_str(&format!("{} {}={} {}hint{}: {}\n", padding, self.paint(BLUE), self.paint(RESET), self.paint(BOLD), self.paint(RESET), hint));
+        }
+
+        out
+    }
+
+    /// Renders each diagnostic followed by a summary of how many errors and warnings there were
+    pub fn render_all<'a>(&mut self, diagnostics: impl IntoIterator<Item = &'a Diagnostic>) -> String
+    {
+        let mut out = String::new();
+        let mut errors = 0;
+        let mut warnings = 0;
+
+        for diagnostic in diagnostics
+        {
+            match diagnostic.severity
+            {
+                Severity::Warning => warnings += 1,
+                Severity::Error => errors += 1,
+            }
+
+            out.push_str(&self.render(diagnostic));
+            out.push('\n');
+        }
+
+        let count = |n: usize, name: &str| format!("{} {}{}", n, name, if n == 1 { "" } else { "s" });
+        match (errors, warnings)
+        {
+            (0, 0) => {},
+            (0, w) => out.push_str(&format!("{}warning{}: {}\n", self.paint(YELLOW), self.paint(RESET), count(w, "warning"))),
+            (e, 0) => out.push_str(&format!("{}error{}: {}\n", self.paint(RED), self.paint(RESET), count(e, "error"))),
+            (e, w) => out.push_str(&format!("{}error{}: {}, {}\n", self.paint(RED), self.paint(RESET), count(e, "error"), count(w, "warning"))),
+        }
+
+        out
+    }
+
+    fn paint(&self, code: &'static str) -> &'static str
+    {
+        if self.color { code } else { "" }
+    }
+
+    fn source_line(&mut self, path: &str, line: usize) -> Option<String>
+    {
+        let source = self.sources.entry(path.to_owned())
+            .or_insert_with(|| utils::load_file(path).ok());
+
+        source.as_ref()?
+            .lines()
+            .nth(line.checked_sub(1)?)
+            .map(|l| l.to_owned())
+    }
+}
+
+/// The character offset and length to underline on the source line
+fn underline(text: &str, diagnostic: &Diagnostic) -> Option<(usize, usize)>
+{
+    if let Some(column) = diagnostic.column
+    {
+        return Some((column.saturating_sub(1).min(text.chars().count()), 1));
+    }
+
+    let highlight = diagnostic.highlight.as_ref().filter(|h| !h.is_empty())?;
+
+    // prefer the highlighted text as a complete json string, so `Gen` does not match inside `Genesis`
+    let quoted = serde_json::to_string(highlight).ok()?;
+    let (byte_start, len) = match text.find(&quoted) {
+        Some(i) => (i + 1, quoted.chars().count() - 2),
+        None => (text.find(highlight.as_str())?, highlight.chars().count()),
+    };
+
+    Some((text[..byte_start].chars().count(), len.max(1)))
+}
@@ -0,0 +1,280 @@
+use std::{collections::HashMap, num::NonZeroU32};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{error::LoadError, modules::{bible::{BibleModule, Word}, dict::{DictEntry, DictModule}}, ref_id::{Atom, RefId}, utils};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct DictLink
+{
+    /// A word atom, or a range of word atoms for terms that span several words, such as `Baal peor`
+    pub id: RefId,
+    /// The term of the matched [`DictEntry`]
+    pub term: String,
+    /// The term or alias the Bible text matched against
+    pub matched: String,
+}
+
+/// An index of which words in a Bible have entries in a dictionary.
+#[derive(Debug)]
+pub struct DictLinks
+{
+    pub bible_name: String,
+    pub dict_name: String,
+    links: Vec<DictLink>,
+    words: HashMap<RefId, Vec<usize>>,
+}
+
+struct Candidate<'a>
+{
+    term: &'a str,
+    matched: &'a str,
+    parts: usize,
+    capitalized: bool,
+}
+
+impl DictLinks
+{
+    pub fn build(bible: &BibleModule, dict: &DictModule) -> Self
+    {
+        let mut candidates = HashMap::<String, Vec<Candidate>>::new();
+        for entry in dict.entries.iter()
+        {
+            for name in entry_names(entry)
+            {
+                let parts = name_parts(name);
+                if parts.is_empty()
+                {
+                    continue;
+                }
+
+                candidates.entry(parts.concat()).or_default().push(Candidate {
+                    term: &entry.term,
+                    matched: name,
+                    parts: parts.len(),
+                    capitalized: name.chars().next().is_some_and(|c| c.is_uppercase()),
+                });
+            }
+        }
+
+        let max_parts = candidates.values().flatten().map(|c| c.parts).max().unwrap_or(1);
+
+        let book_order = bible.source.book_infos.values()
+            .map(|b| (b.osis_id.as_str(), b.index))
+            .collect::<HashMap<_, _>>();
+
+        let verses = bible.source.verses.values()
+            .filter_map(|v| v.id.get_verse_components().map(|c| (c, v)))
+            .sorted_by_key(|((book, chapter, verse), _)| (book_order.get(book).copied().unwrap_or(u32::MAX), *chapter, *verse))
+            .collect_vec();
+
+        let mut links = vec![];
+        for ((book, chapter, verse), data) in verses
+        {
+            let words = data.words.iter().map(normalize_word).collect_vec();
+
+            let mut i = 0;
+            while i < words.len()
+            {
+                let capitalized = data.words[i].text.chars().find(|c| c.is_alphabetic()).is_some_and(|c| c.is_uppercase());
+                let found = (1..=max_parts.min(words.len() - i)).rev().find_map(|len| {
+                    if words[i..i + len].iter().any(|w| w.is_empty())
+                    {
+                        return None;
+                    }
+
+                    let matches = candidates.get(&words[i..i + len].concat())?.iter()
+                        .filter(|c| c.parts >= len && (capitalized || !c.capitalized))
+                        .collect_vec();
+
+                    (!matches.is_empty()).then_some((len, matches))
+                });
+
+                let Some((len, matches)) = found else {
+                    i += 1;
+                    continue;
+                };
+
+                let from = word_atom(book, chapter, verse, i);
+                let id = match len
+                {
+                    1 => RefId::Single(from),
+                    _ => RefId::Range { from, to: word_atom(book, chapter, verse, i + len - 1) },
+                };
+
+                for m in matches.into_iter().unique_by(|m| m.term)
+                {
+                    links.push(DictLink {
+                        id: id.clone(),
+                        term: m.term.to_owned(),
+                        matched: m.matched.to_owned(),
+                    });
+                }
+
+                i += len;
+            }
+        }
+
+        Self::from_links(bible.name.clone(), dict.name.clone(), links)
+    }
+
+    pub fn load(path: &str, bible_name: &str, dict_name: &str) -> Result<Self, LoadError>
+    {
+        let links = utils::load_json_lines(path)?
+            .into_iter()
+            .map(|(l, _)| l)
+            .collect();
+
+        Ok(Self::from_links(bible_name.to_owned(), dict_name.to_owned(), links))
+    }
+
+    /// Writes the links as json lines, so that they can be used without the Bible and dictionary
+    pub fn save(&self, path: &str) -> Result<(), LoadError>
+    {
+        utils::write_json_lines(path, &self.links)
+    }
+
+    pub fn links(&self) -> &[DictLink]
+    {
+        &self.links
+    }
+
+    /// Gets the links for a single word, e.g. `John.1.6#6`
+    pub fn get(&self, word: &RefId) -> Vec<&DictLink>
+    {
+        self.words.get(word)
+            .map(|l| l.iter().map(|i| &self.links[*i]).collect())
+            .unwrap_or_default()
+    }
+
+    /// Gets all links within a book, chapter or verse, e.g. `John.1`
+    pub fn find_in(&self, id: &RefId) -> Vec<&DictLink>
+    {
+        let RefId::Single(scope) = id else {
+            return self.links.iter().filter(|l| in_range(id, ref_start(&l.id))).collect();
+        };
+
+        self.links.iter().filter(|l| in_scope(scope, ref_start(&l.id))).collect()
+    }
+
+    pub fn entry<'a>(&self, link: &DictLink, dict: &'a DictModule) -> Option<&'a DictEntry>
+    {
+        dict.entries.iter().find(|e| e.term == link.term)
+    }
+
+    fn from_links(bible_name: String, dict_name: String, links: Vec<DictLink>) -> Self
+    {
+        let mut words = HashMap::<RefId, Vec<usize>>::new();
+        for (i, link) in links.iter().enumerate()
+        {
+            for word in link_words(&link.id)
+            {
+                words.entry(word).or_default().push(i);
+            }
+        }
+
+        Self { bible_name, dict_name, links, words }
+    }
+}
+
+fn entry_names(entry: &DictEntry) -> impl Iterator<Item = &str>
+{
+    std::iter::once(entry.term.as_str())
+        .chain(entry.aliases.iter().flatten().map(|a| a.as_str()))
+}
+
+// splits a term like `Baal-peor` or `Shiloh (name of a city)` into its normalized parts `baal`, `peor`
+fn name_parts(name: &str) -> Vec<String>
+{
+    let name = match name.split_once('(') {
+        Some((name, _)) => name,
+        None => name,
+    };
+
+    name.split(|c: char| c.is_whitespace() || c == '-')
+        .map(|p| p.chars().filter(|c| c.is_alphanumeric()).flat_map(|c| c.to_lowercase()).collect::<String>())
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+fn normalize_word(word: &Word) -> String
+{
+    let text = word.text.strip_suffix("'s").unwrap_or(&word.text);
+    text.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+fn word_atom(book: &str, chapter: u32, verse: u32, index: usize) -> Atom
+{
+    Atom::Word {
+        book: book.to_owned(),
+        chapter: NonZeroU32::new(chapter).unwrap(),
+        verse: NonZeroU32::new(verse).unwrap(),
+        word: NonZeroU32::new(index as u32 + 1).unwrap(),
+    }
+}
+
+fn ref_start(id: &RefId) -> &Atom
+{
+    match id
+    {
+        RefId::Single(atom) => atom,
+        RefId::Range { from, to: _ } => from,
+    }
+}
+
+fn link_words(id: &RefId) -> Vec<RefId>
+{
+    match id
+    {
+        RefId::Single(atom) => vec![RefId::Single(atom.clone())],
+        RefId::Range { from, to } => {
+            let (Some(start), Some(end)) = (from.word(), to.word()) else {
+                return vec![RefId::Single(from.clone()), RefId::Single(to.clone())];
+            };
+
+            (start.get()..=end.get()).filter_map(|w| {
+                let word = NonZeroU32::new(w)?;
+                let (book, chapter, verse) = (from.book().to_owned(), from.chapter()?, from.verse()?);
+                Some(RefId::Single(Atom::Word { book, chapter, verse, word }))
+            }).collect()
+        }
+    }
+}
+
+fn in_scope(scope: &Atom, atom: &Atom) -> bool
+{
+    scope.book() == atom.book()
+        && scope.chapter().is_none_or(|c| Some(c) == atom.chapter())
+        && scope.verse().is_none_or(|v| Some(v) == atom.verse())
+        && scope.word().is_none_or(|w| Some(w) == atom.word())
+}
+
+fn in_range(range: &RefId, atom: &Atom) -> bool
+{
+    let RefId::Range { from, to } = range else {
+        return false;
+    };
+
+    if from.book() != atom.book() || to.book() != atom.book()
+    {
+        return false;
+    }
+
+    let key = |a: &Atom| (a.chapter().map_or(0, |c| c.get()), a.verse().map_or(0, |v| v.get()));
+    let (from_key, to_key) = (key(from), key(to));
+    let (chapter, verse) = key(atom);
+
+    let after_start = (chapter, verse) >= from_key;
+    let before_end = match (to.chapter(), to.verse()) {
+        (None, _) => true,
+        (Some(_), None) => chapter <= to_key.0,
+        (Some(_), Some(_)) => (chapter, verse) <= to_key,
+    };
+
+    after_start && before_end
+}
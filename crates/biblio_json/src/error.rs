@@ -0,0 +1,275 @@
+use std::{fmt::Display, io::ErrorKind, num::NonZeroUsize};
+
+use crate::ref_id::RefId;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderingError
+{
+    /// The id is not in the format `book.chapter.verse`
+    InvalidId
+    {
+        id: RefId,
+    },
+    /// A book appears again after other books were defined
+    Book
+    {
+        book: String,
+    },
+    Chapter
+    {
+        id: RefId,
+        expected: u32,
+    },
+    Verse
+    {
+        id: RefId,
+        expected: u32,
+    },
+}
+
+/// A package file that does not match the package manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityError
+{
+    /// The file is listed in the manifest but does not exist
+    Missing,
+    Size
+    {
+        expected: u64,
+        found: u64,
+    },
+    Checksum
+    {
+        expected: String,
+        found: String,
+    },
+}
+
+/// An error produced while reading or writing package files. Line and column numbers are 1 based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadError
+{
+    Io
+    {
+        path: String,
+        kind: ErrorKind,
+        message: String,
+    },
+    Toml
+    {
+        path: String,
+        line: Option<usize>,
+        column: Option<usize>,
+        message: String,
+    },
+    JsonLine
+    {
+        path: String,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    Ordering
+    {
+        path: String,
+        line: usize,
+        error: OrderingError,
+    },
+    Config
+    {
+        path: String,
+        line: Option<usize>,
+        message: String,
+    },
+    Integrity
+    {
+        path: String,
+        error: IntegrityError,
+    },
+    Glob
+    {
+        pattern: String,
+        message: String,
+    },
+    /// A value could not be serialized, or a file could not be encoded, while saving
+    Write
+    {
+        path: String,
+        message: String,
+    },
+}
+
+impl LoadError
+{
+    pub fn io(path: impl Display, error: &std::io::Error) -> Self
+    {
+        Self::Io { path: path.to_string(), kind: error.kind(), message: error.to_string() }
+    }
+
+    pub fn toml(path: impl Display, src: &str, error: &toml::de::Error) -> Self
+    {
+        let position = error.span().map(|span| line_column(src, span.start));
+
+        Self::Toml {
+            path: path.to_string(),
+            line: position.map(|(l, _)| l),
+            column: position.map(|(_, c)| c),
+            message: error.message().to_owned()
+        }
+    }
+
+    pub fn json_line(path: impl Display, line: usize, error: &serde_json::Error) -> Self
+    {
+        Self::JsonLine { path: path.to_string(), line, column: error.column(), message: error.to_string() }
+    }
+
+    pub fn config(path: impl Display, line: Option<usize>, message: impl Into<String>) -> Self
+    {
+        Self::Config { path: path.to_string(), line, message: message.into() }
+    }
+
+    pub fn write(path: impl Display, error: impl Display) -> Self
+    {
+        Self::Write { path: path.to_string(), message: error.to_string() }
+    }
+
+    /// The file or glob pattern the error occurred in
+    pub fn path(&self) -> &str
+    {
+        match self
+        {
+            Self::Io { path, kind: _, message: _ } => path,
+            Self::Toml { path, line: _, column: _, message: _ } => path,
+            Self::JsonLine { path, line: _, column: _, message: _ } => path,
+            Self::Ordering { path, line: _, error: _ } => path,
+            Self::Config { path, line: _, message: _ } => path,
+            Self::Integrity { path, error: _ } => path,
+            Self::Glob { pattern, message: _ } => pattern,
+            Self::Write { path, message: _ } => path,
+        }
+    }
+
+    pub(crate) fn replace_path_prefix(&mut self, from: &str, to: &str)
+    {
+        let path = match self
+        {
+            Self::Io { path, kind: _, message: _ } => path,
+            Self::Toml { path, line: _, column: _, message: _ } => path,
+            Self::JsonLine { path, line: _, column: _, message: _ } => path,
+            Self::Ordering { path, line: _, error: _ } => path,
+            Self::Config { path, line: _, message: _ } => path,
+            Self::Integrity { path, error: _ } => path,
+            Self::Glob { pattern, message: _ } => pattern,
+            Self::Write { path, message: _ } => path,
+        };
+
+        if let Some(rest) = path.strip_prefix(from)
+        {
+            *path = format!("{}{}", to, rest);
+        }
+    }
+
+    pub fn line(&self) -> Option<usize>
+    {
+        match self
+        {
+            Self::Toml { path: _, line, column: _, message: _ } => *line,
+            Self::JsonLine { path: _, line, column: _, message: _ } => Some(*line),
+            Self::Ordering { path: _, line, error: _ } => Some(*line),
+            Self::Config { path: _, line, message: _ } => *line,
+            _ => None,
+        }
+    }
+
+    pub fn column(&self) -> Option<usize>
+    {
+        match self
+        {
+            Self::Toml { path: _, line: _, column, message: _ } => *column,
+            Self::JsonLine { path: _, line: _, column, message: _ } => Some(*column),
+            _ => None,
+        }
+    }
+}
+
+impl Display for LoadError
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        match self
+        {
+            Self::Io { path, kind: _, message } => write!(f, "Could not access {}: {}", path, message),
+            Self::Toml { path, line, column, message } => match (line, column) {
+                (Some(line), Some(column)) => write!(f, "Invalid toml in {} on line {}, column {}: {}", path, line, column, message),
+                _ => write!(f, "Invalid toml in {}: {}", path, message),
+            },
+            Self::JsonLine { path, line, column, message } => write!(f, "Invalid json in {} on line {}, column {}: {}", path, line, column, message),
+            Self::Ordering { path, line, error } => match error {
+                OrderingError::InvalidId { id } => write!(f, "Verse {} in file {} on line {}, is not in the format `book.chapter.verse`.", id, path, line),
+                OrderingError::Book { book } => write!(f, "Book {} in file {} on line {}, has already been defined and is out of order.", book, path, line),
+                OrderingError::Chapter { id, expected: _ } => write!(f, "Verse {} in file {} on line {}, has a chapter number that is out of order.", id, path, line),
+                OrderingError::Verse { id, expected: _ } => write!(f, "Verse {} in file {} on line {}, has a verse number that is out of order.", id, path, line),
+            },
+            Self::Config { path, line, message } => match line {
+                Some(line) => write!(f, "{} ({} on line {})", message, path, line),
+                None => write!(f, "{} ({})", message, path),
+            },
+            Self::Integrity { path, error } => match error {
+                IntegrityError::Missing => write!(f, "File {} is listed in the package manifest but does not exist", path),
+                IntegrityError::Size { expected, found } => write!(f, "File {} is {} bytes, but the package manifest expects {} bytes", path, found, expected),
+                IntegrityError::Checksum { expected, found } => write!(f, "File {} has SHA-256 {}, but the package manifest expects {}", path, found, expected),
+            },
+            Self::Glob { pattern, message } => write!(f, "Invalid module path {}: {}", pattern, message),
+            Self::Write { path, message } => write!(f, "Could not write {}: {}", path, message),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// Gathers load errors, ignoring any past the limit
+#[derive(Debug)]
+pub(crate) struct ErrorCollector
+{
+    pub errors: Vec<LoadError>,
+    limit: Option<NonZeroUsize>,
+}
+
+impl ErrorCollector
+{
+    pub fn new(limit: Option<NonZeroUsize>) -> Self
+    {
+        Self { errors: vec![], limit }
+    }
+
+    pub fn push(&mut self, error: LoadError)
+    {
+        if !self.is_full()
+        {
+            self.errors.push(error);
+        }
+    }
+
+    pub fn extend(&mut self, errors: impl IntoIterator<Item = LoadError>)
+    {
+        errors.into_iter().for_each(|e| self.push(e));
+    }
+
+    pub fn is_full(&self) -> bool
+    {
+        self.limit.is_some_and(|l| self.errors.len() >= l.get())
+    }
+
+    pub fn finish<T>(self, value: T) -> Result<T, Vec<LoadError>>
+    {
+        if self.errors.is_empty() { Ok(value) } else { Err(self.errors) }
+    }
+}
+
+fn line_column(src: &str, offset: usize) -> (usize, usize)
+{
+    let before = src.get(..offset).unwrap_or(src);
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+    (line, column)
+}
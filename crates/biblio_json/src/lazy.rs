@@ -0,0 +1,133 @@
+use std::{fmt::Debug, sync::{Mutex, OnceLock}};
+
+use crate::{error::LoadError, modules::{Module, ModuleConfig, ModuleKind}};
+
+type Loader<T> = dyn Fn() -> Result<T, Vec<LoadError>> + Send + Sync;
+
+/// A value that is loaded the first time it is needed and cached until [`DataCell::unload`] is called.
+/// Threads that ask for the value while it is being loaded wait for it rather than loading it again.
+pub struct DataCell<T>
+{
+    value: OnceLock<T>,
+    loading: Mutex<()>,
+    loader: Option<Box<Loader<T>>>,
+}
+
+impl<T> DataCell<T>
+{
+    pub fn new(loader: impl Fn() -> Result<T, Vec<LoadError>> + Send + Sync + 'static) -> Self
+    {
+        Self { value: OnceLock::new(), loading: Mutex::new(()), loader: Some(Box::new(loader)) }
+    }
+
+    /// A cell holding a value that was not loaded from anywhere, so it is never unloaded
+    pub fn loaded(value: T) -> Self
+    {
+        Self { value: OnceLock::from(value), loading: Mutex::new(()), loader: None }
+    }
+
+    /// The cached value, loading it first if needed. Failed loads are not cached, so the next call tries again.
+    pub fn get(&self) -> Result<&T, Vec<LoadError>>
+    {
+        if let Some(value) = self.value.get()
+        {
+            return Ok(value);
+        }
+
+        let _loading = self.loading.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(value) = self.value.get()
+        {
+            return Ok(value);
+        }
+
+        // a cell is only ever empty if it has a loader
+        let loaded = (self.loader.as_ref().unwrap())()?;
+        Ok(self.value.get_or_init(|| loaded))
+    }
+
+    /// Calls `f` with the cached value, or with a freshly loaded one that is dropped afterwards rather than cached
+    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> Result<R, Vec<LoadError>>
+    {
+        match self.value.get()
+        {
+            Some(value) => Ok(f(value)),
+            None => Ok(f(&(self.loader.as_ref().unwrap())()?)),
+        }
+    }
+
+    pub fn is_loaded(&self) -> bool
+    {
+        self.value.get().is_some()
+    }
+
+    /// Drops the cached value, so it is loaded again when next needed. Values made with [`DataCell::loaded`] are kept.
+    pub fn unload(&mut self)
+    {
+        if self.loader.is_some()
+        {
+            self.value.take();
+        }
+    }
+}
+
+impl<T> Debug for DataCell<T>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        f.debug_struct("DataCell").field("loaded", &self.is_loaded()).finish()
+    }
+}
+
+/// A module of a [`crate::Package`]. Its config is read straight away, and with [`crate::LoadOptions::lazy`]
+/// its data is only loaded the first time [`PackageModule::get`] is called.
+#[derive(Debug)]
+pub struct PackageModule
+{
+    pub id: String,
+    pub config: ModuleConfig,
+    data: DataCell<Module>,
+}
+
+impl PackageModule
+{
+    pub(crate) fn new(id: String, config: ModuleConfig, data: DataCell<Module>) -> Self
+    {
+        Self { id, config, data }
+    }
+
+    /// A module that is already in memory, such as one made with a builder
+    pub fn loaded(module: Module) -> Self
+    {
+        Self { id: module.id().to_owned(), config: module.config(), data: DataCell::loaded(module) }
+    }
+
+    pub fn kind(&self) -> ModuleKind
+    {
+        self.config.kind()
+    }
+
+    pub fn name(&self) -> &str
+    {
+        self.config.name()
+    }
+
+    pub fn get(&self) -> Result<&Module, Vec<LoadError>>
+    {
+        self.data.get()
+    }
+
+    pub(crate) fn with<R>(&self, f: impl FnOnce(&Module) -> R) -> Result<R, Vec<LoadError>>
+    {
+        self.data.with(f)
+    }
+
+    pub fn is_loaded(&self) -> bool
+    {
+        self.data.is_loaded()
+    }
+
+    pub fn unload(&mut self)
+    {
+        self.data.unload()
+    }
+}
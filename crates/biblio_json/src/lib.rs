@@ -1,12 +1,29 @@
 pub(crate) mod utils;
+pub mod archive;
+pub mod builder;
+pub mod cache;
+pub mod citations;
+pub mod dependencies;
+pub mod diagnostics;
+pub mod dict_links;
+pub mod error;
+pub mod lazy;
+pub mod library;
+pub mod lints;
+pub mod manifest;
+pub mod markup;
 pub mod modules;
 pub mod ref_id;
-use std::{fmt::Display, path::Path};
+pub mod xref_graph;
+pub mod xref_merge;
+use std::{collections::{BTreeMap, HashMap, HashSet}, fmt::Display, num::NonZeroUsize, path::{Path, PathBuf}, sync::{atomic::{AtomicUsize, Ordering}, Arc, Mutex}};
 
 use itertools::Itertools;
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+use semver::Version;
 use serde::{Deserialize, Serialize};
 
-use crate::{modules::{bible::BibleModule, dict::DictModule, xrefs::{XRef, XRefModule}, Module}, ref_id::RefId};
+use crate::{archive::{ArchiveFormat, ExtractedArchive}, dependencies::{Dependency, DependencySpec, LoadedPackages, Registry}, diagnostics::Severity, error::{ErrorCollector, LoadError}, lazy::{DataCell, PackageModule}, lints::{LintLevel, LintReport}, manifest::Manifest, markup::{Markup, MarkupError}, modules::{bible::BibleModule, dict::{normalize_term, DictEntry, DictModule}, lexicon::LexiconModule, xrefs::{XRef, XRefModule}, module_id, Module, ModuleKind}, ref_id::RefId};
 
 pub const PACKAGE_FILE_NAME: &str = "biblio-json.toml";
 
@@ -15,9 +32,13 @@ pub const PACKAGE_FILE_NAME: &str = "biblio-json.toml";
 pub struct PackageConfig
 {
     pub name: String,
+    pub version: Option<Version>,
     pub authors: Vec<String>,
     pub license: String,
     pub module_paths: Option<ModulePaths>,
+    pub dependencies: Option<BTreeMap<String, DependencySpec>>,
+    /// Overrides the level of lints by name, e.g. `missing_pub_year = "deny"`
+    pub lints: Option<BTreeMap<String, LintLevel>>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -27,16 +48,184 @@ pub struct ModulePaths
     pub bibles: Option<String>,
     pub dictionaries: Option<String>,
     pub xrefs: Option<String>,
+    pub lexicons: Option<String>,
+}
+
+/// Controls how errors are reported while loading
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions
+{
+    /// Keep going after the first error in a file, so every malformed line, ordering problem and missing book name is reported in one pass
+    pub accumulate: bool,
+    /// Stop loading once this many errors have been found. Not zero, as a load that keeps no errors could not report failing
+    pub error_limit: Option<NonZeroUsize>,
+    /// Where to find dependencies that do not have a `path`
+    pub registry: Option<Registry>,
+    /// Only read module configs, loading the data of each module the first time it is used
+    pub lazy: bool,
+    /// Read finished modules from the binary cache next to each json lines file when it is up to date, writing a new cache when it is not.
+    /// Caches are never used for packages loaded from an archive, or for packages with a manifest.
+    pub cache: bool,
+}
+
+impl LoadOptions
+{
+    pub fn accumulate() -> Self
+    {
+        Self { accumulate: true, error_limit: None, registry: None, lazy: false, cache: false }
+    }
+
+    pub fn lazy() -> Self
+    {
+        Self { accumulate: false, error_limit: None, registry: None, lazy: true, cache: false }
+    }
+
+    pub fn with_error_limit(self, error_limit: NonZeroUsize) -> Self
+    {
+        Self { error_limit: Some(error_limit), ..self }
+    }
+
+    pub fn with_registry(self, registry: Registry) -> Self
+    {
+        Self { registry: Some(registry), ..self }
+    }
+
+    pub fn with_cache(self) -> Self
+    {
+        Self { cache: true, ..self }
+    }
+
+    pub(crate) fn file_limit(&self) -> Option<NonZeroUsize>
+    {
+        if self.accumulate { self.error_limit } else { Some(NonZeroUsize::MIN) }
+    }
 }
 
 pub enum PackageValidationError
 {
+    DuplicateModuleId
+    {
+        id: String,
+    },
+    ModuleLoad
+    {
+        id: String,
+        error: LoadError,
+    },
+    MissingBibleDep
+    {
+        xref_name: String,
+    },
+    UnknownBibleDep
+    {
+        xref_name: String,
+        bible_dep: String,
+    },
+    MissingDictBibleDep
+    {
+        dict_name: String,
+    },
+    UnknownDictBibleDep
+    {
+        dict_name: String,
+        bible_dep: String,
+    },
+    InvalidChain
+    {
+        xref_name: String,
+        path: String,
+        topic: String,
+        line: usize,
+        reason: String,
+    },
     InvalidRefId
     {
         id: RefId,
         bible_name: String,
         xref_name: String,
+        path: String,
+        line: usize,
+    },
+    InvalidDictRefId
+    {
+        id: RefId,
+        bible_name: String,
+        dict_name: String,
+        path: String,
+        term: String,
+        line: usize,
+    },
+    InvalidMarkup
+    {
+        dict_name: String,
+        path: String,
+        term: String,
         line: usize,
+        error: MarkupError,
+    },
+    UnresolvedEntryLink
+    {
+        dict_name: String,
+        path: String,
+        term: String,
+        target: String,
+        line: usize,
+    },
+    DuplicateDictTerm
+    {
+        dict_name: String,
+        path: String,
+        term: String,
+        line: usize,
+        first_line: usize,
+    },
+    DictAliasCollision
+    {
+        dict_name: String,
+        path: String,
+        term: String,
+        alias: String,
+        other_term: String,
+        line: usize,
+    },
+    EmptyDefinition
+    {
+        dict_name: String,
+        path: String,
+        term: String,
+        line: usize,
+    },
+    DictHeaderRow
+    {
+        dict_name: String,
+        path: String,
+        term: String,
+        line: usize,
+    },
+    DictWhitespace
+    {
+        dict_name: String,
+        path: String,
+        term: String,
+        text: String,
+        line: usize,
+    },
+}
+
+impl PackageValidationError
+{
+    /// Problems that do not stop the package from being used, such as formatting issues, are warnings
+    pub fn severity(&self) -> Severity
+    {
+        match self
+        {
+            Self::MissingBibleDep { .. } |
+            Self::MissingDictBibleDep { .. } |
+            Self::EmptyDefinition { .. } |
+            Self::DictHeaderRow { .. } |
+            Self::DictWhitespace { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
     }
 }
 
@@ -46,9 +235,54 @@ impl Display for PackageValidationError
     {
         match self 
         {
-            Self::InvalidRefId { id, bible_name, xref_name, line } => {
-                write!(f, "RefId {} in xref module {} on line {} does not exist in Bible {}", id, xref_name, line, bible_name)
-            }
+            Self::DuplicateModuleId { id } => {
+                write!(f, "Module id {} is used by more than one module", id)
+            },
+            Self::ModuleLoad { id, error } => {
+                write!(f, "Module {} could not be loaded: {}", id, error)
+            },
+            Self::MissingBibleDep { xref_name } => {
+                write!(f, "Xref module {} does not declare a `bible_dep`, so its references cannot be validated", xref_name)
+            },
+            Self::UnknownBibleDep { xref_name, bible_dep } => {
+                write!(f, "Xref module {} depends on Bible {}, but there is no Bible with that id in the package or its dependencies", xref_name, bible_dep)
+            },
+            Self::MissingDictBibleDep { dict_name } => {
+                write!(f, "Dictionary {} references verses but does not declare a `bible_dep`, so its references cannot be validated", dict_name)
+            },
+            Self::UnknownDictBibleDep { dict_name, bible_dep } => {
+                write!(f, "Dictionary {} depends on Bible {}, but there is no Bible with that id in the package or its dependencies", dict_name, bible_dep)
+            },
+            Self::InvalidChain { xref_name, path, topic, line, reason } => {
+                write!(f, "Chain {} in xref module {} ({}) on line {} is invalid: {}", topic, xref_name, path, line, reason)
+            },
+            Self::InvalidRefId { id, bible_name, xref_name, path, line } => {
+                write!(f, "RefId {} in xref module {} ({}) on line {} does not exist in Bible {}", id, xref_name, path, line, bible_name)
+            },
+            Self::InvalidDictRefId { id, bible_name, dict_name, path, term, line } => {
+                write!(f, "RefId {} in entry {} of dictionary {} ({}) on line {} does not exist in Bible {}", id, term, dict_name, path, line, bible_name)
+            },
+            Self::InvalidMarkup { dict_name, path, term, line, error } => {
+                write!(f, "Definition of {} in dictionary {} ({}) on line {} has invalid markup: {}", term, dict_name, path, line, error)
+            },
+            Self::UnresolvedEntryLink { dict_name, path, term, target, line } => {
+                write!(f, "Entry {} in dictionary {} ({}) on line {} links to {}, which does not exist", term, dict_name, path, line, target)
+            },
+            Self::DuplicateDictTerm { dict_name, path, term, line, first_line } => {
+                write!(f, "Term {} in dictionary {} ({}) on line {} was already defined on line {}", term, dict_name, path, line, first_line)
+            },
+            Self::DictAliasCollision { dict_name, path, term, alias, other_term, line } => {
+                write!(f, "Alias {} of term {} in dictionary {} ({}) on line {} collides with the term {}", alias, term, dict_name, path, line, other_term)
+            },
+            Self::EmptyDefinition { dict_name, path, term, line } => {
+                write!(f, "Term {} in dictionary {} ({}) on line {} has an empty definition", term, dict_name, path, line)
+            },
+            Self::DictHeaderRow { dict_name, path, term, line } => {
+                write!(f, "Entry {} in dictionary {} ({}) on line {} looks like a header row", term, dict_name, path, line)
+            },
+            Self::DictWhitespace { dict_name, path, term, text, line } => {
+                write!(f, "Text {:?} of entry {} in dictionary {} ({}) on line {} has leading, trailing or repeated whitespace", text, term, dict_name, path, line)
+            },
         }
     }
 }
@@ -57,144 +291,383 @@ impl Display for PackageValidationError
 pub struct Package 
 {
     pub name: String,
+    pub version: Option<Version>,
     pub authors: Vec<String>,
     pub license: String,
-    pub modules: Vec<Module>
+    pub modules: Vec<PackageModule>,
+    pub lints: BTreeMap<String, LintLevel>,
+    pub dependencies: Vec<Dependency>,
+    /// Files in the package that are not listed in its manifest, relative to the package directory
+    pub unlisted_files: Vec<String>,
 }
 
 impl Package
 {
-    pub fn load(dir_path: &str) -> Result<Self, Vec<String>>
+    /// Loads a package from its directory, or from a `.zip` or `.tar.gz` archive made with [`archive::pack`]
+    pub fn load(dir_path: &str) -> Result<Self, Vec<LoadError>>
+    {
+        Self::load_with(dir_path, &LoadOptions::default())
+    }
+
+    /// Loads the package and its dependencies. Dependencies without a `path` are looked up in [`LoadOptions::registry`].
+    pub fn load_with(dir_path: &str, options: &LoadOptions) -> Result<Self, Vec<LoadError>>
+    {
+        Self::load_resolving(dir_path, options, &mut vec![], &LoadedPackages::default())
+    }
+
+    /// Loads the package unless it is already in `loaded`, so a package that several others depend on is only loaded once
+    pub(crate) fn load_shared(dir_path: &str, options: &LoadOptions, stack: &mut Vec<PathBuf>, loaded: &LoadedPackages) -> Result<Arc<Self>, Vec<LoadError>>
+    {
+        let canonical = Path::new(dir_path).canonicalize().map_err(|e| vec![LoadError::io(dir_path, &e)])?;
+        if let Some(package) = loaded.get(&canonical)
+        {
+            return package;
+        }
+
+        let package = Self::load_resolving(dir_path, options, stack, loaded).map(Arc::new);
+        loaded.insert(canonical, package.clone());
+        package
+    }
+
+    /// `stack` holds the canonical paths of the packages being loaded, as a dependency key need not match the name of the package it points to
+    fn load_resolving(dir_path: &str, options: &LoadOptions, stack: &mut Vec<PathBuf>, loaded: &LoadedPackages) -> Result<Self, Vec<LoadError>>
+    {
+        let canonical = Path::new(dir_path).canonicalize().map_err(|e| vec![LoadError::io(dir_path, &e)])?;
+
+        stack.push(canonical);
+        let package = Self::load_path(dir_path, options, stack, loaded);
+        stack.pop();
+
+        package
+    }
+
+    fn load_path(dir_path: &str, options: &LoadOptions, stack: &mut Vec<PathBuf>, loaded: &LoadedPackages) -> Result<Self, Vec<LoadError>>
     {
         let path = Path::new(dir_path);
 
+        if path.is_file() && let Some(format) = ArchiveFormat::from_path(path)
+        {
+            let extracted = Arc::new(archive::extract(path, format).map_err(|e| vec![e])?);
+            let base_dir = path.parent().unwrap_or(Path::new(""));
+
+            // the extracted files are temporary, so a cache written next to them would never be read
+            let options = LoadOptions { cache: false, ..options.clone() };
+
+            return Self::load_dir(&extracted.root(), base_dir, Some(&extracted), &options, stack, loaded)
+                .map_err(|errors| extracted.restore_paths(errors));
+        }
+
         if !path.is_dir()
         {
-            return Err(vec![format!("Provided path: {dir_path}, must be a directory")]);
+            return Err(vec![LoadError::config(dir_path, None, "Provided package path must be a directory or a package archive")]);
         }
 
+        Self::load_dir(dir_path, path, None, options, stack, loaded)
+    }
+
+    /// `archive` is the archive the package was extracted from, which modules keep alive until they are dropped
+    fn load_dir(dir_path: &str, base_dir: &Path, archive: Option<&Arc<ExtractedArchive>>, options: &LoadOptions, stack: &mut Vec<PathBuf>, loaded: &LoadedPackages) -> Result<Self, Vec<LoadError>>
+    {
+        let path = Path::new(dir_path);
         let config_path = path.join(Path::new(PACKAGE_FILE_NAME));
-        let file = utils::load_file(config_path).map_err(|e| vec![e])?;
-        let config = match toml::from_str::<PackageConfig>(&file) {
-            Ok(ok) => ok,
-            Err(e) => return Err(vec![e.to_string()])
-        };
+        let config: PackageConfig = utils::load_toml(config_path).map_err(|e| vec![e])?;
+
+        let mut unlisted_files = vec![];
+        let mut module_options = options.clone();
+        if let Some(manifest) = Manifest::load(path).map_err(|e| vec![e])?
+        {
+            manifest.verify(path)?;
+            unlisted_files = manifest.unlisted(path).map_err(|e| vec![e])?;
+
+            // caches are not listed in the manifest, so modules are only read from the files that were just verified
+            module_options.cache = false;
+        }
 
         let modules = match &config.module_paths {
-            Some(paths) => Self::load_modules(dir_path, paths)?,
-            None => vec![]
+            Some(paths) => Self::load_modules(dir_path, paths, archive, &module_options),
+            None => Ok(vec![])
+        };
+
+        let dependencies = match &config.dependencies {
+            Some(specs) => dependencies::load_dependencies(dir_path, base_dir, specs, options.registry.as_ref(), stack, |path, stack| {
+                Self::load_shared(path, options, stack, loaded)
+            }),
+            None => Ok(vec![])
+        };
+
+        let (modules, dependencies) = match (modules, dependencies) {
+            (Ok(modules), Ok(dependencies)) => (modules, dependencies),
+            (modules, dependencies) => {
+                return Err(modules.err().into_iter().chain(dependencies.err()).flatten().collect());
+            }
         };
 
+        let duplicates = duplicate_ids(modules.iter().map(|m| m.id.as_str()));
+        if !duplicates.is_empty()
+        {
+            return Err(duplicates.into_iter().map(|id| {
+                LoadError::config(dir_path, None, format!("Module id {} is used by more than one module, set a unique `id` in their configs", id))
+            }).collect());
+        }
+
         Ok(Self {
             name: config.name,
+            version: config.version,
             authors: config.authors,
             license: config.license,
-            modules
+            modules,
+            lints: config.lints.unwrap_or_default(),
+            dependencies,
+            unlisted_files,
         })
     }
 
-    fn load_modules(root: &str, paths: &ModulePaths) -> Result<Vec<Module>, Vec<String>>
+    /// Loads every module in parallel, across files and module types. Modules are returned in the same order as if they were
+    /// loaded one at a time: Bibles, then dictionaries, cross references and lexicons, each in the order of their config paths.
+    fn load_modules(root: &str, paths: &ModulePaths, archive: Option<&Arc<ExtractedArchive>>, options: &LoadOptions) -> Result<Vec<PackageModule>, Vec<LoadError>>
     {
-        let mut modules = vec![];
-        let mut errors = vec![];
-        
-        if let Some(bibles_path) = &paths.bibles
+        let kinds = [
+            (&paths.bibles, ModuleKind::Bible),
+            (&paths.dictionaries, ModuleKind::Dictionary),
+            (&paths.xrefs, ModuleKind::XRef),
+            (&paths.lexicons, ModuleKind::Lexicon),
+        ];
+
+        let entries = kinds.into_iter()
+            .filter_map(|(pattern, kind)| pattern.as_ref().map(|p| (p, kind)))
+            .flat_map(|(pattern, kind)| Self::module_entries(root, pattern, kind))
+            .collect_vec();
+
+        Self::load_entries(entries, options, |kind, dir, name, options| Self::read_module(kind, dir, name, archive, options))
+    }
+
+    /// The module with this id, loading its data if it has not been loaded yet. Modules that fail to load are treated as missing,
+    /// [`Package::package_module`] gives their errors.
+    pub fn module(&self, id: &str) -> Option<&Module>
+    {
+        self.package_module(id)?.get().ok()
+    }
+
+    pub fn bible(&self, id: &str) -> Option<&BibleModule>
+    {
+        match self.module_of_kind(id, ModuleKind::Bible)?
         {
-            let result = Self::load_module(root, &bibles_path, |dir, name| 
-            {
-                Ok(Module::Bible(BibleModule::load(dir, name)?))
-            });
+            Module::Bible(b) => Some(b),
+            _ => None,
+        }
+    }
 
-            match result
-            {
-                Ok(ok) => modules.extend(ok),
-                Err(e) => errors.push(e),
-            }
+    pub fn dictionary(&self, id: &str) -> Option<&DictModule>
+    {
+        match self.module_of_kind(id, ModuleKind::Dictionary)?
+        {
+            Module::Dictionary(d) => Some(d),
+            _ => None,
         }
+    }
 
-        if let Some(dictionary_paths) = &paths.dictionaries
+    pub fn xrefs(&self, id: &str) -> Option<&XRefModule>
+    {
+        match self.module_of_kind(id, ModuleKind::XRef)?
         {
-            let result =  Self::load_module(root, &dictionary_paths, |dir, name| 
-            {
-                Ok(Module::Dictionary(DictModule::load(dir, name)?))
-            });
+            Module::XRef(x) => Some(x),
+            _ => None,
+        }
+    }
 
-            match result
-            {
-                Ok(ok) => modules.extend(ok),
-                Err(e) => errors.push(e),
-            }
+    pub fn lexicon(&self, id: &str) -> Option<&LexiconModule>
+    {
+        match self.module_of_kind(id, ModuleKind::Lexicon)?
+        {
+            Module::Lexicon(l) => Some(l),
+            _ => None,
         }
+    }
+
+    /// The module with this id without loading its data
+    pub fn package_module(&self, id: &str) -> Option<&PackageModule>
+    {
+        self.modules.iter().find(|m| m.id == id)
+    }
+
+    pub fn module_ids(&self) -> impl Iterator<Item = &str>
+    {
+        self.modules.iter().map(|m| m.id.as_str())
+    }
 
-        if let Some(xref_paths) = &paths.xrefs
+    /// Only loads the module if it has the right kind
+    fn module_of_kind(&self, id: &str, kind: ModuleKind) -> Option<&Module>
+    {
+        self.modules.iter().find(|m| m.id == id && m.kind() == kind)?.get().ok()
+    }
+
+    /// Finds a Bible by id in this package or in one of its dependencies. A `dependency/bible` id only looks in that dependency.
+    pub fn find_bible(&self, id: &str) -> Option<&BibleModule>
+    {
+        if let Some((dependency, id)) = id.split_once('/')
         {
-            let result =  Self::load_module(root, &xref_paths, |dir, name| 
+            return self.dependencies.iter().find(|d| d.name == dependency)?.package.bible(id);
+        }
+
+        self.bible(id).or_else(|| self.dependencies.iter().find_map(|d| d.package.bible(id)))
+    }
+
+    /// Writes the package config and every module to `dir_path`. Modules are put in a `bibles`, `dictionaries`, `cross_references` or `lexicons`
+    /// directory depending on their type, named after their module id.
+    pub fn save(&self, dir_path: &str) -> Result<(), LoadError>
+    {
+        let mut paths = ModulePaths { bibles: None, dictionaries: None, xrefs: None, lexicons: None };
+        let mut written = HashSet::new();
+
+        let mut save_module = |module: &Module| -> Result<(), LoadError> {
+            let (dir, pattern) = match module
             {
-                Ok(Module::XRef(XRefModule::load(dir, name)?))
-            });
+                Module::Bible(_) => ("bibles", &mut paths.bibles),
+                Module::Dictionary(_) => ("dictionaries", &mut paths.dictionaries),
+                Module::XRef(_) => ("cross_references", &mut paths.xrefs),
+                Module::Lexicon(_) => ("lexicons", &mut paths.lexicons),
+            };
 
-            match result
+            let stem = module.id();
+            let module_dir = format!("{}/{}", dir_path, dir);
+            if !written.insert(stem.to_owned())
             {
-                Ok(ok) => modules.extend(ok),
-                Err(e) => errors.push(e),
+                return Err(LoadError::config(format!("{}/{}.toml", module_dir, stem), None, format!("Module id {} is used by more than one module", stem)));
             }
-        }
 
-        if errors.len() > 0
-        {
-            Err(errors)
-        }
-        else 
+            *pattern = Some(format!("{}/*.toml", dir));
+
+            match module
+            {
+                Module::Bible(b) => b.save(&module_dir, stem),
+                Module::Dictionary(d) => d.save(&module_dir, stem),
+                Module::XRef(x) => x.save(&module_dir, stem),
+                Module::Lexicon(l) => l.save(&module_dir, stem),
+            }
+        };
+
+        for module in self.modules.iter()
         {
-            Ok(modules)    
+            // modules that are not loaded are only loaded while they are written, so one is held in memory at a time
+            module.with(&mut save_module).map_err(utils::first_error)??;
         }
+
+        let has_modules = !self.modules.is_empty();
+        let config = PackageConfig {
+            name: self.name.clone(),
+            version: self.version.clone(),
+            authors: self.authors.clone(),
+            license: self.license.clone(),
+            module_paths: has_modules.then_some(paths),
+            dependencies: (!self.dependencies.is_empty()).then(|| {
+                self.dependencies.iter().map(|d| (d.name.clone(), d.spec.clone())).collect()
+            }),
+            lints: (!self.lints.is_empty()).then(|| self.lints.clone()),
+        };
+
+        utils::write_toml(Path::new(dir_path).join(PACKAGE_FILE_NAME), &config)
+    }
+
+    /// Runs the lints in [`lints::LINTS`], at the levels configured in the `[lints]` table of the package config
+    pub fn lint(&self) -> LintReport
+    {
+        lints::lint_package(self, &self.lints)
     }
 
+    /// Checks the contents of every module, loading the ones that have not been loaded yet
     pub fn validate(&self) -> Result<(), Vec<PackageValidationError>>
     {
-        let mut errors = vec![];
+        let mut errors = duplicate_ids(self.module_ids()).into_iter()
+            .map(|id| PackageValidationError::DuplicateModuleId { id })
+            .collect_vec();
 
-        let bibles = self.modules.iter().filter_map(|m| match m {
-            Module::Bible(b) => Some(b),
+        let mut modules = vec![];
+        for module in self.modules.iter()
+        {
+            match module.get()
+            {
+                Ok(ok) => modules.push(ok),
+                Err(e) => errors.extend(e.into_iter().map(|error| PackageValidationError::ModuleLoad { id: module.id.clone(), error })),
+            }
+        }
+
+        let xrefs = modules.iter().filter_map(|m| match m {
+            Module::XRef(b) => Some(b),
             _ => None,
         }).collect_vec();
 
-        let xrefs = self.modules.iter().filter_map(|m| match m {
-            Module::XRef(b) => Some(b),
+        let dicts = modules.iter().filter_map(|m| match m {
+            Module::Dictionary(d) => Some(d),
             _ => None,
         }).collect_vec();
 
-        for bible in bibles
+        for xref in &xrefs
+        {
+            Self::validate_chains(xref, &mut errors);
+
+            let Some(bible_dep) = &xref.bible_dep else {
+                errors.push(PackageValidationError::MissingBibleDep { xref_name: xref.name.clone() });
+                continue;
+            };
+
+            let Some(bible) = self.find_bible(bible_dep) else {
+                errors.push(PackageValidationError::UnknownBibleDep { 
+                    xref_name: xref.name.clone(), 
+                    bible_dep: bible_dep.clone() 
+                });
+                continue;
+            };
+
+            Self::validate_xrefs(xref, bible, &mut errors);
+        }
+
+        // the definitions of each entry, parsed once for both the reference and link checks. Dictionaries that
+        // do not opt in to markup are plain text, so braces in older data are not reported as invalid markup
+        let markups = dicts.iter().map(|dict| {
+            dict.entries.iter().map(|entry| if dict.has_markup() { entry.parse_definitions() } else { vec![] }).collect_vec()
+        }).collect_vec();
+
+        for (dict, markup) in dicts.iter().zip(markups.iter())
         {
-            let bible_name = &bible.name;
-            for xref in &xrefs
+            Self::validate_dict_refs(dict, markup, self, &mut errors);
+        }
+
+        for (dict, markup) in dicts.iter().zip(markups)
+        {
+            Self::validate_dict(dict, &mut errors);
+
+            for (i, (entry, definitions)) in dict.entries.iter().zip(markup).enumerate()
             {
-                let xref_name = &xref.name;
+                for markup in definitions
+                {
+                    let markup = match markup {
+                        Ok(ok) => ok,
+                        Err(error) => {
+                            errors.push(PackageValidationError::InvalidMarkup { 
+                                dict_name: dict.name.clone(), 
+                                path: dict.path.clone(), 
+                                term: entry.term.clone(), 
+                                line: dict.line(i), 
+                                error 
+                            });
+                            continue;
+                        }
+                    };
 
-                xref.refs.iter().enumerate().map(|(i, r)| match r {
-                    XRef::Directed { source, source_text: _, targets, note: _ } => {
-                        let mut ids = targets.iter().map(|t| (i, t.clone())).collect_vec();
-                        ids.push((i, source.clone()));
-                        ids
-                    },
-                    XRef::Mutual { refs, note: _ } => refs.iter().map(|r| (i, r.id.clone())).collect_vec(),
-                })
-                .flatten()
-                .for_each(|(i, id)| {
-                    if !bible.source.id_exists(&id)
-                    {
-                        errors.push(PackageValidationError::InvalidRefId { 
-                            id: id.clone(), 
-                            bible_name: bible_name.clone(), 
-                            xref_name: xref_name.clone(),
-                            line: i + 1,
+                    markup.entry_links().into_iter().filter(|t| dict.find(t).is_none()).for_each(|target| {
+                        errors.push(PackageValidationError::UnresolvedEntryLink { 
+                            dict_name: dict.name.clone(), 
+                            path: dict.path.clone(), 
+                            term: entry.term.clone(), 
+                            target: target.to_owned(), 
+                            line: dict.line(i),
                         });
-                    }
-                });
+                    });
+                }
             }
         }
 
-        if errors.len() > 0
+        if !errors.is_empty()
         {
             Err(errors)
         }
@@ -204,35 +677,354 @@ impl Package
         }
     }
 
-    fn load_module(base_dir: &str, pattern: &str, f: impl Fn(&str, &str) -> Result<Module, String>) -> Result<Vec<Module>, String>
+    fn validate_chains(xref: &XRefModule, errors: &mut Vec<PackageValidationError>)
     {
-        let full_path = format!("{}/{}", base_dir, pattern);
+        for (i, r) in xref.refs.iter().enumerate()
+        {
+            let XRef::Chain { topic, refs, note: _ } = r else {
+                continue;
+            };
 
-        glob::glob(&full_path).map_err(|e| e.to_string())?.filter_map(|entry| -> Option<Result<Module, String>> {
-            let entry = match entry {
-                Ok(ok) => ok,
-                Err(e) => return Some(Err(e.to_string())),
+            let reason = if refs.len() < 2 {
+                Some("a chain must contain at least two references".to_owned())
+            } else {
+                refs.iter().duplicates().next().map(|d| format!("{} appears more than once", d))
             };
 
-            let path = Path::new(&entry);
+            if let Some(reason) = reason
+            {
+                errors.push(PackageValidationError::InvalidChain { 
+                    xref_name: xref.name.clone(), 
+                    path: xref.path.clone(), 
+                    topic: topic.clone(), 
+                    line: xref.line(i), 
+                    reason 
+                });
+            }
+        }
+    }
+
+    fn validate_xrefs(xref: &XRefModule, bible: &BibleModule, errors: &mut Vec<PackageValidationError>)
+    {
+        xref.refs.iter().enumerate().flat_map(|(i, r)| match r {
+            XRef::Directed { source, source_text: _, targets, note: _ } => {
+                let mut ids = targets.iter().map(|t| (i, t.id.clone())).collect_vec();
+                ids.push((i, source.clone()));
+                ids
+            },
+            XRef::Mutual { refs, note: _ } => refs.iter().map(|r| (i, r.id.clone())).collect_vec(),
+            XRef::Chain { topic: _, refs, note: _ } => refs.iter().map(|r| (i, r.clone())).collect_vec(),
+        })
+        .filter(|(_, id)| !bible.source.id_exists(id))
+        .for_each(|(i, id)| {
+            errors.push(PackageValidationError::InvalidRefId { 
+                id, 
+                bible_name: bible.name.clone(), 
+                xref_name: xref.name.clone(),
+                path: xref.path.clone(),
+                line: xref.line(i),
+            });
+        });
+    }
+
+    /// Checks the declared, cited and markup references of every entry against the `bible_dep` of the dictionary
+    fn validate_dict_refs(dict: &DictModule, markup: &[Vec<Result<Markup, MarkupError>>], package: &Package, errors: &mut Vec<PackageValidationError>)
+    {
+        let entry_refs = dict.entries.iter().zip(markup).enumerate().map(|(i, (entry, definitions))| {
+            let markup_refs = definitions.iter()
+                .flatten()
+                .flat_map(|m| m.ref_links().into_iter().cloned());
+
+            let refs = entry.refs.iter().flatten().cloned()
+                .chain(entry.extract_refs())
+                .chain(markup_refs)
+                .unique()
+                .collect_vec();
+
+            (i, entry, refs)
+        }).filter(|(_, _, refs)| !refs.is_empty()).collect_vec();
+
+        if entry_refs.is_empty()
+        {
+            return;
+        }
+
+        let Some(bible_dep) = &dict.bible_dep else {
+            errors.push(PackageValidationError::MissingDictBibleDep { dict_name: dict.name.clone() });
+            return;
+        };
+
+        let Some(bible) = package.find_bible(bible_dep) else {
+            errors.push(PackageValidationError::UnknownDictBibleDep { 
+                dict_name: dict.name.clone(), 
+                bible_dep: bible_dep.clone() 
+            });
+            return;
+        };
+
+        for (i, entry, refs) in entry_refs
+        {
+            refs.into_iter().filter(|id| !bible.source.id_exists(id)).for_each(|id| {
+                errors.push(PackageValidationError::InvalidDictRefId { 
+                    id, 
+                    bible_name: bible.name.clone(), 
+                    dict_name: dict.name.clone(), 
+                    path: dict.path.clone(), 
+                    term: entry.term.clone(), 
+                    line: dict.line(i),
+                });
+            });
+        }
+    }
+
+    fn validate_dict(dict: &DictModule, errors: &mut Vec<PackageValidationError>)
+    {
+        let mut terms = HashMap::<String, (usize, &str)>::new();
+        for (i, entry) in dict.entries.iter().enumerate()
+        {
+            let line = dict.line(i);
+            let normalized = normalize_term(&entry.term);
+
+            match terms.get(&normalized)
+            {
+                Some((first_line, _)) => errors.push(PackageValidationError::DuplicateDictTerm { 
+                    dict_name: dict.name.clone(), 
+                    path: dict.path.clone(), 
+                    term: entry.term.clone(), 
+                    line, 
+                    first_line: *first_line 
+                }),
+                None => { terms.insert(normalized, (line, &entry.term)); },
+            }
+
+            if entry.definitions.is_empty() || entry.definitions.iter().any(|d| d.trim().is_empty())
+            {
+                errors.push(PackageValidationError::EmptyDefinition { 
+                    dict_name: dict.name.clone(), 
+                    path: dict.path.clone(), 
+                    term: entry.term.clone(), 
+                    line 
+                });
+            }
 
-            let ext = path.extension().map(|s| s.to_str()).flatten();
-            if ext != Some("toml")
+            if is_header_row(entry)
+            {
+                errors.push(PackageValidationError::DictHeaderRow { 
+                    dict_name: dict.name.clone(), 
+                    path: dict.path.clone(), 
+                    term: entry.term.clone(), 
+                    line 
+                });
+            }
+
+            let texts = std::iter::once(&entry.term)
+                .chain(entry.aliases.iter().flatten())
+                .chain(entry.definitions.iter());
+
+            for text in texts.filter(|t| has_whitespace_problem(t))
+            {
+                errors.push(PackageValidationError::DictWhitespace { 
+                    dict_name: dict.name.clone(), 
+                    path: dict.path.clone(), 
+                    term: entry.term.clone(), 
+                    text: text.clone(), 
+                    line 
+                });
+            }
+        }
+
+        for (i, entry) in dict.entries.iter().enumerate()
+        {
+            let own_term = normalize_term(&entry.term);
+            for alias in entry.aliases.iter().flatten()
+            {
+                let normalized = normalize_term(alias);
+                if normalized == own_term
+                {
+                    continue;
+                }
+
+                if let Some((_, other_term)) = terms.get(&normalized)
+                {
+                    errors.push(PackageValidationError::DictAliasCollision { 
+                        dict_name: dict.name.clone(), 
+                        path: dict.path.clone(), 
+                        term: entry.term.clone(), 
+                        alias: alias.clone(), 
+                        other_term: other_term.to_string(), 
+                        line: dict.line(i), 
+                    });
+                }
+            }
+        }
+    }
+
+    /// The kind, directory and name of every module config matching `pattern`, in path order
+    fn module_entries(base_dir: &str, pattern: &str, kind: ModuleKind) -> Vec<Result<(ModuleKind, String, String), Vec<LoadError>>>
+    {
+        let full_path = format!("{}/{}", base_dir, pattern);
+
+        // hidden files are left out of manifests, so wildcards must not match them or they would be loaded without being verified
+        let options = glob::MatchOptions { require_literal_leading_dot: true, ..Default::default() };
+        match glob::glob_with(&full_path, options)
+        {
+            Ok(entries) => entries
+                .filter_map(Self::module_path)
+                .map(|path| path.map(|(dir, name)| (kind, dir, name)))
+                .collect(),
+            Err(e) => vec![Err(vec![LoadError::Glob { pattern: full_path, message: e.to_string() }])],
+        }
+    }
+
+    /// Loads each entry in parallel with `f`, combining the results in the order of the entries.
+    /// Unless errors are being accumulated, only the errors of the first entry to fail are reported, and entries after it are not loaded.
+    /// Each entry may use the whole error limit, and the combined errors are cut off in entry order, so the result does not depend on how
+    /// the loads are scheduled. Entries are not loaded once the entries before them have used up the limit.
+    fn load_entries<M: Send>(
+        entries: Vec<Result<(ModuleKind, String, String), Vec<LoadError>>>,
+        options: &LoadOptions,
+        f: impl Fn(ModuleKind, &str, &str, &LoadOptions) -> Result<M, Vec<LoadError>> + Sync
+    ) -> Result<Vec<M>, Vec<LoadError>>
+    {
+        // entries after these are skipped, as their results would be dropped however the loads were scheduled
+        let first_failure = AtomicUsize::new(usize::MAX);
+        let limit_reached = AtomicUsize::new(usize::MAX);
+        let error_counts = Mutex::new(vec![None; entries.len()]);
+
+        let results = entries.into_par_iter().enumerate().map(|(i, entry)| {
+            if limit_reached.load(Ordering::Relaxed) < i || (!options.accumulate && first_failure.load(Ordering::Relaxed) < i)
             {
                 return None;
             }
-            
-            let dir = match path.parent() {
-                Some(s) => s,
-                None => return Some(Err(format!("Expected path {} to have a parent", path.display())))
-            }.to_str().unwrap();
 
-            let name = match path.file_stem() {
-                Some(s) => s,
-                None => return Some(Err(format!("Expected path {} to have a stem", path.display())))
-            }.to_str().unwrap();
+            let result = entry.and_then(|(kind, dir, name)| f(kind, &dir, &name, options));
+            if result.is_err()
+            {
+                first_failure.fetch_min(i, Ordering::Relaxed);
+            }
+
+            if let Some(limit) = options.error_limit
+            {
+                let mut counts = error_counts.lock().unwrap_or_else(|e| e.into_inner());
+                counts[i] = Some(result.as_ref().err().map_or(0, |e| e.len()));
+
+                // the first entry whose errors, together with every entry before it, reach the limit
+                let reached = counts.iter()
+                    .map_while(|c| *c)
+                    .scan(0, |total, count| { *total += count; Some(*total) })
+                    .position(|total| total >= limit.get());
+
+                if let Some(reached) = reached
+                {
+                    limit_reached.fetch_min(reached, Ordering::Relaxed);
+                }
+            }
+
+            Some(result)
+        }).collect::<Vec<_>>();
+
+        let mut modules = vec![];
+        let mut errors = ErrorCollector::new(options.error_limit);
+        for result in results.into_iter().flatten()
+        {
+            if errors.is_full()
+            {
+                break;
+            }
 
-            Some(f(dir, name))
-        }).collect()
+            match result
+            {
+                Ok(module) => modules.push(module),
+                Err(e) => {
+                    errors.extend(e);
+                    if !options.accumulate
+                    {
+                        break;
+                    }
+                }
+            }
+        }
+
+        errors.finish(modules)
+    }
+
+    /// Reads the config of a module, and its data too unless it is being loaded lazily
+    fn read_module(kind: ModuleKind, dir_path: &str, name: &str, archive: Option<&Arc<ExtractedArchive>>, options: &LoadOptions) -> Result<PackageModule, Vec<LoadError>>
+    {
+        let config = kind.load_config(dir_path, name).map_err(|e| vec![e])?;
+        let id = module_id(config.id(), dir_path, name).map_err(|e| vec![e])?;
+
+        let lazy = options.lazy;
+        let (dir_path, name, archive) = (dir_path.to_owned(), name.to_owned(), archive.cloned());
+        let options = LoadOptions { lazy: false, registry: None, ..options.clone() };
+
+        let data = DataCell::new(move || {
+            kind.load(&dir_path, &name, &options).map_err(|errors| match &archive {
+                Some(archive) => archive.restore_paths(errors),
+                None => errors,
+            })
+        });
+
+        if !lazy
+        {
+            data.get()?;
+        }
+
+        Ok(PackageModule::new(id, config, data))
     }
+
+    /// The directory and name of a module config file, or `None` if the path is not a toml file
+    fn module_path(entry: Result<std::path::PathBuf, glob::GlobError>) -> Option<Result<(String, String), Vec<LoadError>>>
+    {
+        let entry = match entry {
+            Ok(ok) => ok,
+            Err(e) => return Some(Err(vec![LoadError::io(e.path().display(), e.error())])),
+        };
+
+        let path = Path::new(&entry);
+
+        let ext = path.extension().and_then(|s| s.to_str());
+        if ext != Some("toml")
+        {
+            return None;
+        }
+        
+        let dir = match path.parent() {
+            Some(s) => s,
+            None => return Some(Err(vec![LoadError::config(path.display(), None, "Expected module path to have a parent")]))
+        }.to_str().unwrap();
+
+        let name = match path.file_stem() {
+            Some(s) => s,
+            None => return Some(Err(vec![LoadError::config(path.display(), None, "Expected module path to have a stem")]))
+        }.to_str().unwrap();
+
+        Some(Ok((dir.to_owned(), name.to_owned())))
+    }
+}
+
+pub(crate) fn duplicate_ids<'a>(ids: impl Iterator<Item = &'a str>) -> Vec<String>
+{
+    ids.duplicates()
+        .map(|id| id.to_owned())
+        .collect()
+}
+
+fn is_header_row(entry: &DictEntry) -> bool
+{
+    const HEADER_WORDS: &[&str] = &["term", "terms", "word", "name", "label", "headword", "entry", "meaning", "meanings", "definition", "definitions", "description"];
+
+    let is_header_word = |s: &str| HEADER_WORDS.contains(&s.trim().to_lowercase().as_str());
+
+    let term_is_label = entry.term.starts_with(char::is_lowercase) && (entry.term.contains('_') || is_header_word(&entry.term));
+    let definitions_are_labels = !entry.definitions.is_empty() && entry.definitions.iter().all(|d| is_header_word(d));
+
+    term_is_label || definitions_are_labels
+}
+
+fn has_whitespace_problem(text: &str) -> bool
+{
+    text.trim() != text 
+        || text.contains("  ") 
+        || text.chars().any(|c| c.is_whitespace() && c != ' ')
 }
@@ -0,0 +1,252 @@
+use std::{collections::{BTreeMap, HashSet}, fs, path::{Path, PathBuf}, sync::Arc};
+
+use itertools::Itertools;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::{
+    dependencies::{self, LoadedPackages, Registry},
+    error::LoadError,
+    lazy::PackageModule,
+    modules::{bible::BibleModule, dict::DictModule, lexicon::LexiconModule, xrefs::XRefModule, Module, ModuleKind},
+    utils,
+    LoadOptions,
+    Package,
+    PackageConfig,
+    PACKAGE_FILE_NAME
+};
+
+/// A package loaded into a [`Library`]
+#[derive(Debug)]
+pub struct LibraryPackage
+{
+    /// The package name in lowercase, with punctuation and spaces replaced by `_`
+    pub id: String,
+    pub path: PathBuf,
+    /// Shared with the packages that depend on it
+    pub package: Arc<Package>,
+}
+
+/// Every package found under a root directory. Modules are looked up by a qualified `package/module` id,
+/// or by their module id alone when only one package has a module of that kind with that id.
+#[derive(Debug, Default)]
+pub struct Library
+{
+    pub packages: Vec<LibraryPackage>,
+    /// Packages that were not loaded because another package with the same id has a higher version
+    pub shadowed: Vec<PathBuf>,
+    /// The errors of each package that failed to load, by package directory
+    pub errors: Vec<(PathBuf, Vec<LoadError>)>,
+}
+
+impl Library
+{
+    /// Finds every directory under `root` containing a package config. The directories of a package are not searched any further.
+    pub fn discover(root: impl AsRef<Path>) -> Result<Vec<PathBuf>, LoadError>
+    {
+        let mut found = vec![];
+        let mut stack = vec![root.as_ref().to_owned()];
+
+        while let Some(dir) = stack.pop()
+        {
+            if dir.join(PACKAGE_FILE_NAME).is_file()
+            {
+                found.push(dir);
+                continue;
+            }
+
+            for entry in fs::read_dir(&dir).map_err(|e| LoadError::io(dir.display(), &e))?
+            {
+                let entry = entry.map_err(|e| LoadError::io(dir.display(), &e))?;
+                if entry.file_type().map_err(|e| LoadError::io(entry.path().display(), &e))?.is_dir()
+                {
+                    stack.push(entry.path());
+                }
+            }
+        }
+
+        found.sort();
+        Ok(found)
+    }
+
+    pub fn load(root: impl AsRef<Path>) -> Result<Self, Vec<LoadError>>
+    {
+        Self::load_with(root, &LoadOptions::default())
+    }
+
+    /// Loads every package under `root` in parallel. Only the highest version of each package is loaded, and unless [`LoadOptions::registry`]
+    /// is set, dependencies are resolved against the packages in the library, so each package is loaded once however many others depend on it.
+    /// Packages that fail to load are left out, with their errors in [`Library::errors`].
+    pub fn load_with(root: impl AsRef<Path>, options: &LoadOptions) -> Result<Self, Vec<LoadError>>
+    {
+        let dirs = Self::discover(root.as_ref()).map_err(|e| vec![e])?;
+
+        let mut errors = vec![];
+        let mut configs = vec![];
+        for dir in dirs
+        {
+            match utils::load_toml::<PackageConfig, _>(dir.join(PACKAGE_FILE_NAME))
+            {
+                Ok(config) => configs.push((dir, config)),
+                Err(e) => errors.push((dir, vec![e])),
+            }
+        }
+
+        let mut options = options.clone();
+        if options.registry.is_none()
+        {
+            options.registry = Some(Registry::from_configs(root.as_ref(), configs.iter().map(|(dir, config)| (dir.as_path(), config))));
+        }
+
+        // the first package found wins when versions are equal
+        let mut chosen = BTreeMap::<String, (PathBuf, PackageConfig)>::new();
+        let mut shadowed = vec![];
+        for (dir, config) in configs
+        {
+            let id = utils::file_stem(&config.name);
+            match chosen.get(&id)
+            {
+                Some((_, existing)) if existing.version >= config.version => shadowed.push(dir),
+                _ => if let Some((replaced, _)) = chosen.insert(id, (dir, config))
+                {
+                    shadowed.push(replaced);
+                },
+            }
+        }
+
+        let loaded = LoadedPackages::default();
+        let mut packages = vec![];
+        let mut pending = chosen.into_iter().collect_vec();
+
+        while !pending.is_empty()
+        {
+            // a package is loaded after the library packages it depends on, so two threads never load the same dependency.
+            // Nothing is ready when there is a circular dependency, in which case the rest are loaded and the cycle reported
+            let pending_dirs = pending.iter().map(|(_, (dir, _))| canonical(dir)).collect::<HashSet<_>>();
+            let (ready, waiting): (Vec<_>, Vec<_>) = pending.into_iter().partition(|(_, (dir, config))| {
+                dependency_dirs(dir, config, options.registry.as_ref()).all(|d| !pending_dirs.contains(&d))
+            });
+
+            let (ready, waiting) = if ready.is_empty() { (waiting, vec![]) } else { (ready, waiting) };
+
+            let results = ready.into_par_iter()
+                .map(|(id, (dir, _))| {
+                    let package = Package::load_shared(&dir.display().to_string(), &options, &mut vec![], &loaded);
+                    (id, dir, package)
+                })
+                .collect::<Vec<_>>();
+
+            for (id, path, package) in results
+            {
+                match package
+                {
+                    Ok(package) => packages.push(LibraryPackage { id, path, package }),
+                    Err(e) => errors.push((path, e)),
+                }
+            }
+
+            pending = waiting;
+        }
+
+        packages.sort_by(|a, b| a.id.cmp(&b.id));
+        errors.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ok(Self { packages, shadowed, errors })
+    }
+
+    pub fn package(&self, id: &str) -> Option<&Package>
+    {
+        self.packages.iter().find(|p| p.id == id).map(|p| &*p.package)
+    }
+
+    /// Every module, with its qualified `package/module` id. Their data is not loaded until [`PackageModule::get`] is called
+    pub fn modules(&self) -> impl Iterator<Item = (String, &PackageModule)>
+    {
+        self.packages.iter().flat_map(|p| p.package.modules.iter().map(move |m| (format!("{}/{}", p.id, m.id), m)))
+    }
+
+    /// Finds a module by its qualified `package/module` id, or by its module id if no other package has a module with the same id
+    pub fn module(&self, id: &str) -> Option<&Module>
+    {
+        self.module_of_kind(id, None)
+    }
+
+    pub fn bible(&self, id: &str) -> Option<&BibleModule>
+    {
+        match self.module_of_kind(id, Some(ModuleKind::Bible))?
+        {
+            Module::Bible(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn dictionary(&self, id: &str) -> Option<&DictModule>
+    {
+        match self.module_of_kind(id, Some(ModuleKind::Dictionary))?
+        {
+            Module::Dictionary(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    pub fn xrefs(&self, id: &str) -> Option<&XRefModule>
+    {
+        match self.module_of_kind(id, Some(ModuleKind::XRef))?
+        {
+            Module::XRef(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn lexicon(&self, id: &str) -> Option<&LexiconModule>
+    {
+        match self.module_of_kind(id, Some(ModuleKind::Lexicon))?
+        {
+            Module::Lexicon(l) => Some(l),
+            _ => None,
+        }
+    }
+
+    /// Module ids used by more than one package, with the qualified ids that must be used to look them up
+    pub fn conflicts(&self) -> BTreeMap<String, Vec<String>>
+    {
+        let mut ids = BTreeMap::<String, Vec<String>>::new();
+        for (qualified, module) in self.modules()
+        {
+            ids.entry(module.id.clone()).or_default().push(qualified);
+        }
+
+        ids.retain(|_, qualified| qualified.len() > 1);
+        ids
+    }
+
+    /// Matches on the module metadata, so only the module that is found gets loaded
+    fn module_of_kind(&self, id: &str, kind: Option<ModuleKind>) -> Option<&Module>
+    {
+        let (package, id) = match id.split_once('/')
+        {
+            Some((package, module)) => (Some(package), module),
+            None => (None, id),
+        };
+
+        let mut found = self.packages.iter()
+            .filter(|p| package.is_none_or(|package| p.id == package))
+            .flat_map(|p| p.package.modules.iter())
+            .filter(|m| m.id == id && kind.is_none_or(|kind| m.kind() == kind));
+
+        let module = found.next()?;
+        found.next().is_none().then(|| module.get().ok()).flatten()
+    }
+}
+
+fn canonical(dir: &Path) -> PathBuf
+{
+    dir.canonicalize().unwrap_or_else(|_| dir.to_owned())
+}
+
+/// The canonical directories of the dependencies of a package that can be resolved
+fn dependency_dirs<'a>(dir: &'a Path, config: &'a PackageConfig, registry: Option<&'a Registry>) -> impl Iterator<Item = PathBuf> + 'a
+{
+    config.dependencies.iter()
+        .flatten()
+        .filter_map(move |(name, spec)| dependencies::resolve(name, spec, dir, registry).ok())
+        .map(|path| canonical(&path))
+}
@@ -0,0 +1,331 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{modules::ModuleConfig, Package};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintLevel
+{
+    Allow,
+    Warn,
+    Deny,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lint
+{
+    pub name: &'static str,
+    pub default_level: LintLevel,
+    pub description: &'static str,
+}
+
+pub const MISSING_PUB_YEAR: Lint = Lint {
+    name: "missing_pub_year",
+    default_level: LintLevel::Warn,
+    description: "A module does not declare a `pub_year`"
+};
+
+pub const MISSING_AUTHORS: Lint = Lint {
+    name: "missing_authors",
+    default_level: LintLevel::Warn,
+    description: "The package, a dictionary or a lexicon has no `authors`"
+};
+
+pub const MISSING_DESCRIPTION: Lint = Lint {
+    name: "missing_description",
+    default_level: LintLevel::Warn,
+    description: "A module has no `description`"
+};
+
+pub const MISSING_LICENSE: Lint = Lint {
+    name: "missing_license",
+    default_level: LintLevel::Allow,
+    description: "A dictionary, cross reference module or lexicon does not declare a `license`"
+};
+
+pub const NON_SPDX_LICENSE: Lint = Lint {
+    name: "non_spdx_license",
+    default_level: LintLevel::Warn,
+    description: "A license is not a valid SPDX license expression, such as `MIT` or `CC-BY-SA-4.0 OR CC0-1.0`"
+};
+
+pub const UNLISTED_FILE: Lint = Lint {
+    name: "unlisted_file",
+    default_level: LintLevel::Warn,
+    description: "A file in the package is not listed in the package manifest"
+};
+
+pub const UNKNOWN_LINT: Lint = Lint {
+    name: "unknown_lint",
+    default_level: LintLevel::Warn,
+    description: "The `[lints]` table configures a lint that does not exist"
+};
+
+pub const LINTS: &[Lint] = &[MISSING_PUB_YEAR, MISSING_AUTHORS, MISSING_DESCRIPTION, MISSING_LICENSE, NON_SPDX_LICENSE, UNLISTED_FILE, UNKNOWN_LINT];
+
+pub fn find_lint(name: &str) -> Option<&'static Lint>
+{
+    LINTS.iter().find(|l| l.name == name)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LintFinding
+{
+    pub lint: &'static str,
+    pub level: LintLevel,
+    /// The id of the module the finding is about, or `None` for the package itself
+    pub module: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LintReport
+{
+    pub findings: Vec<LintFinding>,
+}
+
+impl LintReport
+{
+    pub fn has_denied(&self) -> bool
+    {
+        self.findings.iter().any(|f| f.level == LintLevel::Deny)
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = &LintFinding>
+    {
+        self.findings.iter().filter(|f| f.level == LintLevel::Warn)
+    }
+
+    pub fn denied(&self) -> impl Iterator<Item = &LintFinding>
+    {
+        self.findings.iter().filter(|f| f.level == LintLevel::Deny)
+    }
+
+    pub fn to_json(&self) -> String
+    {
+        serde_json::to_string_pretty(self).expect("Lint reports should always serialize")
+    }
+}
+
+/// Runs every lint over the package. `levels` overrides the default level of lints by name, as in the `[lints]` table of the package config.
+pub fn lint_package(package: &Package, levels: &BTreeMap<String, LintLevel>) -> LintReport
+{
+    let mut linter = Linter { levels, findings: vec![] };
+
+    for name in levels.keys().filter(|name| find_lint(name).is_none())
+    {
+        linter.report(&UNKNOWN_LINT, None, format!("Unknown lint `{}` in the `[lints]` table", name));
+    }
+
+    if package.authors.is_empty()
+    {
+        linter.report(&MISSING_AUTHORS, None, format!("Package {} has no authors", package.name));
+    }
+
+    if !is_spdx_expression(&package.license)
+    {
+        linter.report(&NON_SPDX_LICENSE, None, format!("License `{}` of package {} is not an SPDX license expression", package.license, package.name));
+    }
+
+    for file in package.unlisted_files.iter()
+    {
+        linter.report(&UNLISTED_FILE, None, format!("File {} is not listed in the package manifest", file));
+    }
+
+    // lints only need module metadata, so modules are linted without loading their data
+    for module in package.modules.iter()
+    {
+        lint_module(config_meta(&module.id, &module.config), &mut linter);
+    }
+
+    LintReport { findings: linter.findings }
+}
+
+struct Linter<'a>
+{
+    levels: &'a BTreeMap<String, LintLevel>,
+    findings: Vec<LintFinding>,
+}
+
+impl Linter<'_>
+{
+    fn report(&mut self, lint: &Lint, module: Option<&str>, message: String)
+    {
+        let level = self.levels.get(lint.name).copied().unwrap_or(lint.default_level);
+        if level == LintLevel::Allow
+        {
+            return;
+        }
+
+        self.findings.push(LintFinding {
+            lint: lint.name,
+            level,
+            module: module.map(|m| m.to_owned()),
+            message
+        });
+    }
+}
+
+struct ModuleMeta<'a>
+{
+    kind: &'static str,
+    id: &'a str,
+    name: &'a str,
+    description: Option<&'a str>,
+    authors: Option<&'a [String]>,
+    pub_year: Option<u32>,
+    license: Option<Option<&'a str>>,
+}
+
+fn config_meta<'a>(id: &'a str, config: &'a ModuleConfig) -> ModuleMeta<'a>
+{
+    match config
+    {
+        ModuleConfig::Bible(b) => ModuleMeta {
+            kind: "Bible",
+            id,
+            name: &b.name,
+            description: Some(&b.description),
+            authors: None,
+            pub_year: b.pub_year,
+            license: None
+        },
+        ModuleConfig::Dictionary(d) => ModuleMeta {
+            kind: "Dictionary",
+            id,
+            name: &d.name,
+            description: d.description.as_deref(),
+            authors: Some(&d.authors),
+            pub_year: d.pub_year,
+            license: Some(d.license.as_deref())
+        },
+        ModuleConfig::XRef(x) => ModuleMeta {
+            kind: "Xref module",
+            id,
+            name: &x.name,
+            description: x.description.as_deref(),
+            authors: None,
+            pub_year: x.pub_year,
+            license: Some(x.license.as_deref())
+        },
+        ModuleConfig::Lexicon(l) => ModuleMeta {
+            kind: "Lexicon",
+            id,
+            name: &l.name,
+            description: l.description.as_deref(),
+            authors: Some(&l.authors),
+            pub_year: l.pub_year,
+            license: Some(l.license.as_deref())
+        },
+    }
+}
+
+fn lint_module(meta: ModuleMeta, linter: &mut Linter)
+{
+    let id = Some(meta.id);
+
+    if meta.pub_year.is_none()
+    {
+        linter.report(&MISSING_PUB_YEAR, id, format!("{} {} does not declare a `pub_year`", meta.kind, meta.name));
+    }
+
+    if meta.authors.is_some_and(|a| a.is_empty())
+    {
+        linter.report(&MISSING_AUTHORS, id, format!("{} {} has no authors", meta.kind, meta.name));
+    }
+
+    if meta.description.is_none_or(|d| d.trim().is_empty())
+    {
+        linter.report(&MISSING_DESCRIPTION, id, format!("{} {} has no description", meta.kind, meta.name));
+    }
+
+    match meta.license
+    {
+        Some(None) => linter.report(&MISSING_LICENSE, id, format!("{} {} does not declare a `license`", meta.kind, meta.name)),
+        Some(Some(license)) if !is_spdx_expression(license) => {
+            linter.report(&NON_SPDX_LICENSE, id, format!("License `{}` of {} {} is not an SPDX license expression", license, meta.kind, meta.name));
+        },
+        _ => {},
+    }
+}
+
+/// Whether `license` is an SPDX license expression, e.g. `MIT`, `Apache-2.0 WITH LLVM-exception` or `(MIT OR CC0-1.0) AND CC-BY-4.0`.
+/// Identifiers are matched case insensitively against the official SPDX license list, and `LicenseRef-` identifiers are always accepted.
+pub fn is_spdx_expression(license: &str) -> bool
+{
+    let spaced = license.replace('(', " ( ").replace(')', " ) ");
+    let tokens = spaced.split_whitespace().collect::<Vec<_>>();
+
+    let mut parser = SpdxParser { tokens: &tokens, position: 0 };
+    parser.expression() && parser.position == tokens.len()
+}
+
+struct SpdxParser<'a>
+{
+    tokens: &'a [&'a str],
+    position: usize,
+}
+
+impl SpdxParser<'_>
+{
+    fn next(&mut self) -> Option<&str>
+    {
+        let token = self.tokens.get(self.position).copied();
+        self.position += 1;
+        token
+    }
+
+    fn peek_is(&self, keyword: &str) -> bool
+    {
+        self.tokens.get(self.position).is_some_and(|t| *t == keyword)
+    }
+
+    fn expression(&mut self) -> bool
+    {
+        if !self.term()
+        {
+            return false;
+        }
+
+        while self.peek_is("AND") || self.peek_is("OR")
+        {
+            self.position += 1;
+            if !self.term()
+            {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn term(&mut self) -> bool
+    {
+        if self.peek_is("(")
+        {
+            self.position += 1;
+            return self.expression() && self.next() == Some(")");
+        }
+
+        let Some(id) = self.next() else {
+            return false;
+        };
+
+        let id = id.strip_suffix('+').unwrap_or(id);
+        let known = id.starts_with("LicenseRef-") || spdx::identifiers::LICENSES.iter().any(|l| l.name.eq_ignore_ascii_case(id));
+        if !known
+        {
+            return false;
+        }
+
+        if self.peek_is("WITH")
+        {
+            self.position += 1;
+            return self.next().is_some_and(|e| spdx::identifiers::EXCEPTIONS.iter().any(|x| x.name.eq_ignore_ascii_case(e)));
+        }
+
+        true
+    }
+}
@@ -0,0 +1,130 @@
+use std::{collections::BTreeSet, fs::{self, File}, io, path::Path};
+
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{cache, error::{IntegrityError, LoadError}, utils};
+
+pub const MANIFEST_FILE_NAME: &str = "biblio-json.manifest.toml";
+
+/// The size and SHA-256 of every file in a package, checked by [`crate::Package::load`] when the package has a manifest.
+/// Hidden files, such as `.DS_Store`, and binary caches are not listed or checked.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Manifest
+{
+    pub files: Vec<ManifestEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ManifestEntry
+{
+    /// Relative to the package directory, with `/` separators
+    pub path: String,
+    pub size: u64,
+    /// Lowercase hex
+    pub sha256: String,
+}
+
+impl Manifest
+{
+    /// Lists every file in the package directory, other than the manifest itself
+    pub fn generate(dir_path: impl AsRef<Path>) -> Result<Self, LoadError>
+    {
+        let dir_path = dir_path.as_ref();
+        let files = listed_files(dir_path)?;
+
+        let files = files.par_iter().map(|path| {
+            let full_path = dir_path.join(path);
+            let size = fs::metadata(&full_path).map_err(|e| LoadError::io(full_path.display(), &e))?.len();
+            Ok(ManifestEntry { path: path.clone(), size, sha256: sha256(&full_path)? })
+        }).collect::<Result<Vec<_>, LoadError>>()?;
+
+        Ok(Self { files })
+    }
+
+    /// The manifest of the package at `dir_path`, or `None` if it has no manifest
+    pub fn load(dir_path: impl AsRef<Path>) -> Result<Option<Self>, LoadError>
+    {
+        let path = dir_path.as_ref().join(MANIFEST_FILE_NAME);
+        if !path.is_file()
+        {
+            return Ok(None);
+        }
+
+        utils::load_toml(path).map(Some)
+    }
+
+    pub fn save(&self, dir_path: impl AsRef<Path>) -> Result<(), LoadError>
+    {
+        utils::write_toml(dir_path.as_ref().join(MANIFEST_FILE_NAME), self)
+    }
+
+    /// Checks every file listed in the manifest against the package at `dir_path`, reporting each file that is missing
+    /// or has a different size or checksum
+    pub fn verify(&self, dir_path: impl AsRef<Path>) -> Result<(), Vec<LoadError>>
+    {
+        let dir_path = dir_path.as_ref();
+        let integrity = |path: &str, error| LoadError::Integrity { path: dir_path.join(path).display().to_string(), error };
+
+        let errors = self.files.par_iter().filter_map(|entry| {
+            let full_path = dir_path.join(&entry.path);
+            let size = match fs::metadata(&full_path) {
+                Ok(ok) if ok.is_file() => ok.len(),
+                Ok(_) => return Some(integrity(&entry.path, IntegrityError::Missing)),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => return Some(integrity(&entry.path, IntegrityError::Missing)),
+                Err(e) => return Some(LoadError::io(full_path.display(), &e)),
+            };
+
+            if size != entry.size
+            {
+                return Some(integrity(&entry.path, IntegrityError::Size { expected: entry.size, found: size }));
+            }
+
+            match sha256(&full_path)
+            {
+                Ok(found) if found.eq_ignore_ascii_case(&entry.sha256) => None,
+                Ok(found) => Some(integrity(&entry.path, IntegrityError::Checksum { expected: entry.sha256.clone(), found })),
+                Err(e) => Some(e),
+            }
+        }).collect::<Vec<_>>();
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// Files in the package at `dir_path` that the manifest does not list. They do not stop the package from loading,
+    /// and are reported by the [`crate::lints::UNLISTED_FILE`] lint instead.
+    pub fn unlisted(&self, dir_path: impl AsRef<Path>) -> Result<Vec<String>, LoadError>
+    {
+        let listed = self.files.iter().map(|e| e.path.as_str()).collect::<BTreeSet<_>>();
+        Ok(listed_files(dir_path.as_ref())?.into_iter().filter(|f| !listed.contains(f.as_str())).collect())
+    }
+}
+
+/// Generates and saves the manifest of the package at `dir_path`, replacing any existing manifest
+pub fn write_manifest(dir_path: impl AsRef<Path>) -> Result<Manifest, LoadError>
+{
+    let manifest = Manifest::generate(dir_path.as_ref())?;
+    manifest.save(dir_path)?;
+    Ok(manifest)
+}
+
+/// The files a manifest lists, relative to the package directory
+fn listed_files(dir_path: &Path) -> Result<Vec<String>, LoadError>
+{
+    Ok(utils::package_files(dir_path)?
+        .iter()
+        .filter(|p| !p.components().any(|c| c.as_os_str().to_string_lossy().starts_with('.')))
+        .filter(|p| !cache::is_cache_file(p))
+        .map(|p| utils::slash_path(p))
+        .filter(|p| p != MANIFEST_FILE_NAME)
+        .collect())
+}
+
+fn sha256(path: &Path) -> Result<String, LoadError>
+{
+    let mut file = File::open(path).map_err(|e| LoadError::io(path.display(), &e))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher).map_err(|e| LoadError::io(path.display(), &e))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
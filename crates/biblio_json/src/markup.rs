@@ -0,0 +1,320 @@
+use std::{fmt::Display, str::FromStr};
+
+use crate::ref_id::RefId;
+
+// Definitions may contain inline markup of the form `{tag:content}` or `{tag:content|display text}`:
+//   {i:...}             italics, may contain nested markup
+//   {heb:...}           Hebrew script
+//   {grk:...}           Greek script
+//   {see:Abram}         a link to another entry in the same dictionary
+//   {ref:Gen.14.18}     a link to a scripture reference
+// The characters `{`, `}`, `|` and `\` can be escaped with a `\`.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Span
+{
+    Text(String),
+    Italic(Vec<Span>),
+    Hebrew(String),
+    Greek(String),
+    EntryLink
+    {
+        term: String,
+        text: Option<String>,
+    },
+    RefLink
+    {
+        id: RefId,
+        text: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkupError
+{
+    pub message: String,
+    /// Character offset into the source text
+    pub offset: usize,
+}
+
+impl Display for MarkupError
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        write!(f, "{} at character {}", self.message, self.offset)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Markup
+{
+    pub spans: Vec<Span>,
+}
+
+impl Markup
+{
+    pub fn parse(src: &str) -> Result<Self, MarkupError>
+    {
+        let chars = src.chars().collect::<Vec<_>>();
+        let mut parser = Parser { chars: &chars, pos: 0 };
+        let spans = parser.parse_spans(false)?;
+
+        Ok(Self { spans })
+    }
+
+    pub fn entry_links(&self) -> Vec<&str>
+    {
+        let mut links = vec![];
+        visit_spans(&self.spans, &mut |s| if let Span::EntryLink { term, text: _ } = s { links.push(term.as_str()) });
+        links
+    }
+
+    pub fn ref_links(&self) -> Vec<&RefId>
+    {
+        let mut links = vec![];
+        visit_spans(&self.spans, &mut |s| if let Span::RefLink { id, text: _ } = s { links.push(id) });
+        links
+    }
+
+    pub fn to_plain_text(&self) -> String
+    {
+        let mut out = String::new();
+        write_plain_text(&self.spans, &mut out);
+        out
+    }
+
+    pub fn to_html(&self) -> String
+    {
+        let mut out = String::new();
+        write_html(&self.spans, &mut out);
+        out
+    }
+}
+
+impl FromStr for Markup
+{
+    type Err = MarkupError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        Self::parse(s)
+    }
+}
+
+struct Parser<'a>
+{
+    chars: &'a [char],
+    pos: usize,
+}
+
+impl<'a> Parser<'a>
+{
+    fn parse_spans(&mut self, nested: bool) -> Result<Vec<Span>, MarkupError>
+    {
+        let mut spans = vec![];
+        let mut text = String::new();
+
+        while let Some(c) = self.peek()
+        {
+            match c
+            {
+                '}' if nested => break,
+                '}' => return Err(self.error(format!("Unexpected `{}`", c))),
+                '{' => {
+                    if !text.is_empty()
+                    {
+                        spans.push(Span::Text(std::mem::take(&mut text)));
+                    }
+                    spans.push(self.parse_tag()?);
+                },
+                _ => text.push(self.parse_char()?),
+            }
+        }
+
+        if !text.is_empty()
+        {
+            spans.push(Span::Text(text));
+        }
+
+        Ok(spans)
+    }
+
+    fn parse_tag(&mut self) -> Result<Span, MarkupError>
+    {
+        let start = self.pos;
+        self.pos += 1;
+
+        let mut tag = String::new();
+        loop
+        {
+            match self.peek()
+            {
+                Some(':') => break,
+                Some(c) if c.is_ascii_alphanumeric() => tag.push(c),
+                _ => return Err(MarkupError { message: "Expected a tag of the form `{tag:...}`".into(), offset: start }),
+            }
+            self.pos += 1;
+        }
+        self.pos += 1;
+
+        let span = match tag.as_str()
+        {
+            "i" => Span::Italic(self.parse_spans(true)?),
+            "heb" => Span::Hebrew(self.parse_text(false)?),
+            "grk" => Span::Greek(self.parse_text(false)?),
+            "see" => {
+                let term = self.parse_text(true)?;
+                let text = self.parse_display_text()?;
+                Span::EntryLink { term, text }
+            },
+            "ref" => {
+                let id_offset = self.pos;
+                let id = self.parse_text(true)?;
+                let id = RefId::from_str(&id).map_err(|e| MarkupError { message: format!("Invalid scripture reference `{}`: {}", id, e), offset: id_offset })?;
+                let text = self.parse_display_text()?;
+                Span::RefLink { id, text }
+            },
+            _ => return Err(MarkupError { message: format!("Unknown tag `{}`", tag), offset: start }),
+        };
+
+        if self.peek() != Some('}')
+        {
+            return Err(MarkupError { message: format!("Unclosed `{{{}:` tag", tag), offset: start });
+        }
+        self.pos += 1;
+
+        Ok(span)
+    }
+
+    fn parse_display_text(&mut self) -> Result<Option<String>, MarkupError>
+    {
+        if self.peek() != Some('|')
+        {
+            return Ok(None);
+        }
+
+        self.pos += 1;
+        self.parse_text(false).map(Some)
+    }
+
+    fn parse_text(&mut self, stop_at_bar: bool) -> Result<String, MarkupError>
+    {
+        let mut text = String::new();
+        while let Some(c) = self.peek()
+        {
+            match c
+            {
+                '}' => break,
+                '|' if stop_at_bar => break,
+                '{' => return Err(self.error("Unexpected `{`".into())),
+                _ => text.push(self.parse_char()?),
+            }
+        }
+
+        Ok(text)
+    }
+
+    fn parse_char(&mut self) -> Result<char, MarkupError>
+    {
+        let c = self.chars[self.pos];
+        self.pos += 1;
+
+        if c != '\\'
+        {
+            return Ok(c);
+        }
+
+        match self.peek()
+        {
+            Some(escaped @ ('{' | '}' | '|' | '\\')) => {
+                self.pos += 1;
+                Ok(escaped)
+            },
+            _ => Err(MarkupError { message: "Invalid escape sequence".into(), offset: self.pos - 1 }),
+        }
+    }
+
+    fn peek(&self) -> Option<char>
+    {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn error(&self, message: String) -> MarkupError
+    {
+        MarkupError { message, offset: self.pos }
+    }
+}
+
+fn visit_spans<'a>(spans: &'a [Span], f: &mut impl FnMut(&'a Span))
+{
+    for span in spans
+    {
+        f(span);
+        if let Span::Italic(inner) = span
+        {
+            visit_spans(inner, f);
+        }
+    }
+}
+
+fn write_plain_text(spans: &[Span], out: &mut String)
+{
+    for span in spans
+    {
+        match span
+        {
+            Span::Text(text) | Span::Hebrew(text) | Span::Greek(text) => out.push_str(text),
+            Span::Italic(inner) => write_plain_text(inner, out),
+            Span::EntryLink { term, text } => out.push_str(text.as_ref().unwrap_or(term)),
+            Span::RefLink { id, text } => match text {
+                Some(text) => out.push_str(text),
+                None => out.push_str(&id.to_string()),
+            },
+        }
+    }
+}
+
+fn write_html(spans: &[Span], out: &mut String)
+{
+    for span in spans
+    {
+        match span
+        {
+            Span::Text(text) => out.push_str(&escape_html(text)),
+            Span::Italic(inner) => {
+                out.push_str("<i>");
+                write_html(inner, out);
+                out.push_str("</i>");
+            },
+            Span::Hebrew(text) => out.push_str(&format!("<span lang=\"he\" dir=\"rtl\">{}</span>", escape_html(text))),
+            Span::Greek(text) => out.push_str(&format!("<span lang=\"grc\">{}</span>", escape_html(text))),
+            Span::EntryLink { term, text } => {
+                let text = text.as_ref().unwrap_or(term);
+                out.push_str(&format!("<a class=\"entry-link\" data-term=\"{}\">{}</a>", escape_html(term), escape_html(text)));
+            },
+            Span::RefLink { id, text } => {
+                let id = id.to_string();
+                let text = text.as_ref().unwrap_or(&id);
+                out.push_str(&format!("<a class=\"ref-link\" data-ref=\"{}\">{}</a>", escape_html(&id), escape_html(text)));
+            },
+        }
+    }
+}
+
+fn escape_html(text: &str) -> String
+{
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars()
+    {
+        match c
+        {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
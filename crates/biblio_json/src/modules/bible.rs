@@ -1,52 +1,111 @@
 use std::{collections::{HashMap, HashSet}, num::NonZeroU32};
 
-use serde::{Deserialize, Serialize};
+use itertools::Itertools;
+use semver::Version;
+use serde::{Deserialize, Serialize, Serializer};
 
-use crate::{ref_id::{Atom, RefId}, utils};
+use crate::{cache, citations, error::{ErrorCollector, LoadError, OrderingError}, modules::{module_id, ModuleConfig}, ref_id::{Atom, RefId}, utils, LoadOptions};
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct BibleConfig
 {
+    pub id: Option<String>,
     pub name: String,
+    pub version: Option<Version>,
     pub description: String,
     pub language: String,
     pub pub_year: Option<u32>,
     pub data_source: Option<String>,
+    #[serde(serialize_with = "serialize_books")]
     pub books: HashMap<String, String>,
 }
 
-#[derive(Debug)]
+// books are written in canonical order, so saving is deterministic
+fn serialize_books<S>(books: &HashMap<String, String>, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer
+{
+    let order = |osis: &str| citations::book_order(osis).unwrap_or(usize::MAX);
+    serializer.collect_map(books.iter().sorted_by(|(a, _), (b, _)| order(a).cmp(&order(b)).then_with(|| a.cmp(b))))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct BibleModule
 {
+    pub id: String,
     pub name: String,
+    pub version: Option<Version>,
     pub description: String,
     pub language: String,
     pub pub_year: Option<u32>,
+    pub data_source: Option<String>,
+    /// The full names of books, keyed by OSIS id
+    pub books: HashMap<String, String>,
     pub source: BibleSource, 
 }
 
 impl BibleModule
 {
-    pub fn load(dir_path: &str, name: &str) -> Result<BibleModule, String>
+    pub fn load(dir_path: &str, name: &str) -> Result<BibleModule, LoadError>
     {
-        let config_path = format!("{}/{}.toml", dir_path, name);
-        let config: BibleConfig = utils::load_toml(config_path)?;
+        Self::load_with(dir_path, name, &LoadOptions::default()).map_err(utils::first_error)
+    }
 
+    pub fn load_with(dir_path: &str, name: &str, options: &LoadOptions) -> Result<BibleModule, Vec<LoadError>>
+    {
+        let config_path = format!("{}/{}.toml", dir_path, name);
         let bible_path = format!("{}/{}.jsonl", dir_path, name);
-        let source = BibleSource::from_file(&bible_path, &config.books)?;
 
-        Ok(Self { 
+        cache::load_cached(&config_path, &bible_path, options, || {
+            let config: BibleConfig = utils::load_toml(&config_path).map_err(|e| vec![e])?;
+            let id = module_id(config.id.as_deref(), dir_path, name).map_err(|e| vec![e])?;
+            let source = BibleSource::from_file_with(&bible_path, &config.books, options)?;
+
+            let mut module = Self::new(config, source);
+            module.id = id;
+
+            Ok(module)
+        })
+    }
+
+    pub fn new(config: BibleConfig, source: BibleSource) -> Self
+    {
+        Self { 
+            id: config.id.unwrap_or_else(|| utils::file_stem(&config.name)),
             name: config.name, 
+            version: config.version,
             description: config.description,
             language: config.language,
             pub_year: config.pub_year,
+            data_source: config.data_source,
+            books: config.books,
             source,
-        })
+        }
+    }
+
+    pub fn config(&self) -> BibleConfig
+    {
+        BibleConfig {
+            id: Some(self.id.clone()),
+            name: self.name.clone(),
+            version: self.version.clone(),
+            description: self.description.clone(),
+            language: self.language.clone(),
+            pub_year: self.pub_year,
+            data_source: self.data_source.clone(),
+            books: self.books.clone(),
+        }
+    }
+
+    /// Writes `{dir}/{name}.toml` and `{dir}/{name}.jsonl`, with the verses in book, chapter and verse order
+    pub fn save(&self, dir_path: &str, name: &str) -> Result<(), LoadError>
+    {
+        ModuleConfig::Bible(self.config()).save(dir_path, name)?;
+        utils::write_json_lines(format!("{}/{}.jsonl", dir_path, name), self.source.verses_in_order())
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct BibleSource
 {
     pub book_infos: HashMap<u32, BookInfo>,
@@ -55,42 +114,76 @@ pub struct BibleSource
 
 impl BibleSource
 {
-    pub fn from_file(path: &str, books: &HashMap<String, String>) -> Result<BibleSource, String>
+    pub fn from_file(path: &str, books: &HashMap<String, String>) -> Result<BibleSource, LoadError>
+    {
+        Self::from_file_with(path, books, &LoadOptions::default()).map_err(utils::first_error)
+    }
+
+    /// Loads the verses in `path`, checking that books, chapters and verses are in order. 
+    /// With [`LoadOptions::accumulate`] set, every malformed line and ordering problem is reported, rather than just the first.
+    pub fn from_file_with(path: &str, books: &HashMap<String, String>, options: &LoadOptions) -> Result<BibleSource, Vec<LoadError>>
+    {
+        let mut errors = ErrorCollector::new(options.file_limit());
+        let verses: Vec<(Verse, usize)> = utils::load_json_lines_with(path, &mut errors);
+        let failed_lines = errors.errors.iter().filter_map(|e| e.line()).collect::<HashSet<_>>();
+
+        let source = Self::from_ordered_verses(path, verses, books, &failed_lines, &mut errors);
+        errors.finish(source)
+    }
+
+    /// Builds a source from verses that are not read from a file, running the same ordering and book name checks as [`BibleSource::from_file_with`].
+    /// Errors use `source_name` as their path and the 1 based position of the verse as their line.
+    pub fn from_verses(source_name: &str, verses: Vec<Verse>, books: &HashMap<String, String>, options: &LoadOptions) -> Result<BibleSource, Vec<LoadError>>
     {
-        let verses: Vec<(Verse, usize)> = utils::load_json_lines(path)?;
+        let mut errors = ErrorCollector::new(options.file_limit());
+        let verses = verses.into_iter().enumerate().map(|(i, v)| (v, i)).collect();
+
+        let source = Self::from_ordered_verses(source_name, verses, books, &HashSet::new(), &mut errors);
+        errors.finish(source)
+    }
 
+    /// `verses` are paired with their 0 based line, and `failed_lines` are the 1 based lines that could not be parsed
+    fn from_ordered_verses(path: &str, verses: Vec<(Verse, usize)>, books: &HashMap<String, String>, failed_lines: &HashSet<usize>, errors: &mut ErrorCollector) -> BibleSource
+    {
         let mut visited_books = HashSet::<String>::new();
         let mut current_book: Option<&str> = None;
         let mut book_chapters: Vec<u32> = vec![];
+        let mut previous_line: Option<usize> = None;
 
         let mut book_infos = HashMap::new();
 
         for (verse, line) in verses.iter()
         {
+            if errors.is_full()
+            {
+                break;
+            }
+
+            // a verse right after a line that failed to parse is not checked for order, as the missing verse has already been reported
+            let mut reported = (previous_line.map_or(0, |l| l + 1)..*line).any(|l| failed_lines.contains(&(l + 1)));
+            previous_line = Some(*line);
+
             let Some((book, chapter, verse_idx)) = verse.id.get_verse_components() else {
-                return Err(format!("Verse {} in file {} on line {}, is not in the format `book.chapter.verse`.", &verse.id, path, line + 1));
+                errors.push(LoadError::Ordering { path: path.to_owned(), line: line + 1, error: OrderingError::InvalidId { id: verse.id.clone() } });
+                continue;
             };
 
             if Some(book) != current_book
             {
                 if let Some(old_book) = current_book
                 {
-                    let Some(name) = books.get(old_book) else {
-                        // line not +1 because it is referring to the previous line
-                        return Err(format!("Full book name for {} in file {} on line {}, does not exist in the bible config.", old_book, path, line))
-                    };
-
-                    book_infos.insert(visited_books.len() as u32, BookInfo {
-                        name: name.clone(),
-                        osis_id: old_book.to_owned(),
-                        index: visited_books.len() as u32,
-                        chapters: book_chapters,
-                    });
+                    // line not +1 because it is referring to the previous line
+                    match Self::book_info(path, *line, old_book, book_chapters, books, visited_books.len() as u32)
+                    {
+                        Ok(info) => { book_infos.insert(info.index, info); },
+                        Err(e) => errors.push(e),
+                    }
                 }
 
                 if !visited_books.insert(book.to_owned())
                 {
-                    return Err(format!("Book {} in file {} on line {}, has already been defined and is out of order.", book, path, line + 1));
+                    errors.push(LoadError::Ordering { path: path.to_owned(), line: line + 1, error: OrderingError::Book { book: book.to_owned() } });
+                    reported = true;
                 }
 
                 current_book = Some(book);
@@ -103,45 +196,85 @@ impl BibleSource
             }
             else if chapter != book_chapters.len() as u32 
             {
-                return Err(format!("Verse {} in file {} on line {}, has a chapter number that is out of order.", verse.id, path, line))
-            }
+                if !reported
+                {
+                    errors.push(LoadError::Ordering { 
+                        path: path.to_owned(), 
+                        line: line + 1, 
+                        error: OrderingError::Chapter { id: verse.id.clone(), expected: book_chapters.len() as u32 + 1 } 
+                    });
+                }
 
-            if verse_idx == *book_chapters.last().unwrap() + 1
-            {
-                *book_chapters.last_mut().unwrap() += 1;
+                // continue from the chapter that was found, so the following verses are not reported as well
+                reported = true;
+                book_chapters.resize(chapter as usize, 0);
             }
-            else 
+
+            let last = book_chapters.last_mut().unwrap();
+            if verse_idx != *last + 1 && !reported
             {
-                return Err(format!("Verse {} in file {} on line {}, has a verse number that is out of order.", verse.id, path, line))
+                errors.push(LoadError::Ordering { 
+                    path: path.to_owned(), 
+                    line: line + 1, 
+                    error: OrderingError::Verse { id: verse.id.clone(), expected: *last + 1 } 
+                });
             }
+
+            *last = verse_idx;
         }
 
         if let Some(old_book) = current_book
         {
-            let Some(name) = books.get(old_book) else {
-                // line not +1 because it is referring to the previous line
-                return Err(format!("Full book name for {} in file {} on line {}, does not exist in the bible config.", old_book, path, verses.len()))
-            };
-
-            book_infos.insert(visited_books.len() as u32, BookInfo {
-                name: name.clone(),
-                osis_id: old_book.to_owned(),
-                index: visited_books.len() as u32,
-                chapters: book_chapters,
-            });
+            let line = verses.last().map_or(0, |(_, l)| l + 1);
+            match Self::book_info(path, line, old_book, book_chapters, books, visited_books.len() as u32)
+            {
+                Ok(info) => { book_infos.insert(info.index, info); },
+                Err(e) => errors.push(e),
+            }
         }
 
         let verses = verses.into_iter()
             .map(|(v, _)| (v.id.clone(), v))
             .collect::<HashMap<_, _>>();
 
-        Ok(Self 
+        Self 
         {
             book_infos,
             verses,
+        }
+    }
+
+    fn book_info(path: &str, line: usize, osis_id: &str, chapters: Vec<u32>, books: &HashMap<String, String>, index: u32) -> Result<BookInfo, LoadError>
+    {
+        let Some(name) = books.get(osis_id) else {
+            return Err(LoadError::config(path, Some(line), format!("Full book name for {} does not exist in the bible config.", osis_id)));
+        };
+
+        Ok(BookInfo {
+            name: name.clone(),
+            osis_id: osis_id.to_owned(),
+            index,
+            chapters,
         })
     }
 
+    pub fn verses_in_order(&self) -> Vec<&Verse>
+    {
+        self.book_infos.values()
+            .sorted_by_key(|b| b.index)
+            .flat_map(|b| b.chapters.iter().enumerate().flat_map(move |(c, verses)| (1..=*verses).map(move |v| (b, c as u32 + 1, v))))
+            .filter_map(|(book, chapter, verse)| {
+                let id = RefId::Single(Atom::Verse { 
+                    book: book.osis_id.clone(), 
+                    chapter: NonZeroU32::new(chapter)?, 
+                    verse: NonZeroU32::new(verse)? 
+                });
+
+                self.verses.get(&id)
+            })
+            .collect()
+    }
+
     pub fn id_exists(&self, id: &RefId) -> bool
     {
         match id 
@@ -186,14 +319,18 @@ pub struct Verse
 #[serde(rename_all = "snake_case")]
 pub struct Word 
 {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub red: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub italics: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub begin_punc: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub end_punc: Option<String>,
     pub text: String, 
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct BookInfo
 {
     pub name: String,
@@ -1,32 +1,42 @@
 use itertools::{EitherOrBoth, Itertools};
+use semver::Version;
 use serde::{Deserialize, Serialize};
 
-use crate::utils;
+use crate::{cache, citations, error::{ErrorCollector, LoadError}, markup::{Markup, MarkupError}, modules::{module_id, ModuleConfig}, ref_id::RefId, utils, LoadOptions};
 
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DictConfig
 {
+    pub id: Option<String>,
     pub name: String,
+    pub version: Option<Version>,
     pub authors: Vec<String>,
     pub language: String,
     pub description: Option<String>,
     pub data_source: Option<String>,
     pub pub_year: Option<u32>,
     pub license: Option<String>,
+    /// The Bible that references in the entries are validated against
+    pub bible_dep: Option<String>,
+    /// Whether the definitions are written in the definition markup. Otherwise they are plain text, and braces have no meaning
+    pub markup: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DictEntry
 {
     pub term: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub aliases: Option<Vec<String>>,
     pub definitions: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refs: Option<Vec<RefId>>,
 }
 
 impl DictEntry
 {
-    pub fn from_file(path: &str) -> Result<Vec<Self>, String>
+    pub fn from_file(path: &str) -> Result<Vec<Self>, LoadError>
     {
         let ret = utils::load_json_lines(path)?
             .into_iter()
@@ -35,39 +45,140 @@ impl DictEntry
 
         Ok(ret)
     }
+
+    pub fn parse_definitions(&self) -> Vec<Result<Markup, MarkupError>>
+    {
+        self.definitions.iter().map(|d| Markup::parse(d)).collect()
+    }
+
+    pub fn extract_refs(&self) -> Vec<RefId>
+    {
+        self.definitions.iter()
+            .flat_map(|d| citations::extract_refs(d))
+            .unique()
+            .collect()
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct DictModule
 {
+    pub id: String,
     pub name: String,
+    pub version: Option<Version>,
     pub authors: Vec<String>,
     pub language: String,
     pub description: Option<String>,
     pub pub_year: Option<u32>,
     pub license: Option<String>,
+    pub data_source: Option<String>,
     pub entries: Vec<DictEntry>,
+    pub bible_dep: Option<String>,
+    pub markup: Option<bool>,
+    /// Path of the json lines file the entries were loaded from
+    #[serde(skip)]
+    pub path: String,
+    lines: Vec<usize>,
 }
 
 impl DictModule
 {
-    pub fn load(dir_path: &str, name: &str) -> Result<Self, String>
+    pub fn load(dir_path: &str, name: &str) -> Result<Self, LoadError>
     {
-        let config_path = format!("{}/{}.toml", dir_path, name);
-        let config: DictConfig = utils::load_toml(config_path)?;
+        Self::load_with(dir_path, name, &LoadOptions::default()).map_err(utils::first_error)
+    }
 
+    pub fn load_with(dir_path: &str, name: &str, options: &LoadOptions) -> Result<Self, Vec<LoadError>>
+    {
+        let config_path = format!("{}/{}.toml", dir_path, name);
         let dictionary_path = format!("{}/{}.jsonl", dir_path, name);
-        let entries = DictEntry::from_file(&dictionary_path)?;
 
-        Ok(Self { 
+        let mut module = cache::load_cached(&config_path, &dictionary_path, options, || {
+            let config: DictConfig = utils::load_toml(&config_path).map_err(|e| vec![e])?;
+            let id = module_id(config.id.as_deref(), dir_path, name).map_err(|e| vec![e])?;
+
+            let mut errors = ErrorCollector::new(options.file_limit());
+            let (entries, lines) = utils::load_json_lines_with(&dictionary_path, &mut errors).into_iter().unzip();
+
+            let mut module = Self::new(config, entries);
+            module.id = id;
+            module.lines = lines;
+
+            errors.finish(module)
+        })?;
+
+        // not cached, as the package may have moved since the cache was made
+        module.path = dictionary_path;
+        Ok(module)
+    }
+
+    /// A dictionary that was not loaded from a file, so [`DictModule::path`] is empty
+    pub fn new(config: DictConfig, entries: Vec<DictEntry>) -> Self
+    {
+        Self { 
+            id: config.id.unwrap_or_else(|| utils::file_stem(&config.name)),
             name: config.name, 
+            version: config.version,
             authors: config.authors,
             description: config.description,
             language: config.language,
             pub_year: config.pub_year,
             license: config.license,
+            data_source: config.data_source,
             entries,
-        })
+            bible_dep: config.bible_dep,
+            markup: config.markup,
+            path: String::new(),
+            lines: vec![],
+        }
+    }
+
+    pub fn config(&self) -> DictConfig
+    {
+        DictConfig {
+            id: Some(self.id.clone()),
+            name: self.name.clone(),
+            version: self.version.clone(),
+            authors: self.authors.clone(),
+            language: self.language.clone(),
+            description: self.description.clone(),
+            data_source: self.data_source.clone(),
+            pub_year: self.pub_year,
+            license: self.license.clone(),
+            bible_dep: self.bible_dep.clone(),
+            markup: self.markup,
+        }
+    }
+
+    pub fn has_markup(&self) -> bool
+    {
+        self.markup == Some(true)
+    }
+
+    /// Writes `{dir}/{name}.toml` and `{dir}/{name}.jsonl`, with the entries in their current order
+    pub fn save(&self, dir_path: &str, name: &str) -> Result<(), LoadError>
+    {
+        ModuleConfig::Dictionary(self.config()).save(dir_path, name)?;
+        utils::write_json_lines(format!("{}/{}.jsonl", dir_path, name), &self.entries)
+    }
+
+    /// The 1 based line number of the entry at `index` in [`DictModule::path`]
+    pub fn line(&self, index: usize) -> usize
+    {
+        self.lines.get(index).map_or(index, |l| *l) + 1
+    }
+
+    /// Fills in [`DictEntry::refs`] from citations found in the definitions, for entries that do not declare any.
+    pub fn extract_refs(&mut self)
+    {
+        for entry in self.entries.iter_mut().filter(|e| e.refs.is_none())
+        {
+            let refs = entry.extract_refs();
+            if !refs.is_empty()
+            {
+                entry.refs = Some(refs);
+            }
+        }
     }
 
     pub fn find(&self, term: &str) -> Option<&DictEntry>
@@ -79,6 +190,11 @@ impl DictModule
     }
 }
 
+pub(crate) fn normalize_term(s: &str) -> String
+{
+    get_normalized_str_chars(s).collect()
+}
+
 fn eq_ignore_punc_and_case(a: &str, b: &str) -> bool
 {
     let a_chars = get_normalized_str_chars(a);
@@ -101,7 +217,7 @@ fn eq_ignore_punc_and_case(a: &str, b: &str) -> bool
 
 fn get_normalized_str_chars(s: &str) -> impl Iterator<Item = char>
 {
-    s.chars().into_iter()
+    s.chars()
         .filter(|c| c.is_alphanumeric() || *c == '-')
         .map(|c| c.to_ascii_lowercase())
 }
\ No newline at end of file
@@ -0,0 +1,285 @@
+use std::{collections::{hash_map::Entry, HashMap}, fmt, str::FromStr};
+
+use semver::Version;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{cache, error::{ErrorCollector, LoadError}, modules::{module_id, ModuleConfig}, utils, LoadOptions};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct LexiconConfig
+{
+    pub id: Option<String>,
+    pub name: String,
+    pub version: Option<Version>,
+    pub authors: Vec<String>,
+    pub language: String,
+    pub description: Option<String>,
+    pub data_source: Option<String>,
+    pub pub_year: Option<u32>,
+    pub license: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum StrongsLanguage
+{
+    Hebrew,
+    Greek,
+}
+
+/// A Strong's number such as `H430`, `G25` or `H1254a`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StrongsNumber
+{
+    pub language: StrongsLanguage,
+    pub number: u32,
+    pub suffix: Option<char>,
+}
+
+impl fmt::Display for StrongsNumber
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        let prefix = match self.language
+        {
+            StrongsLanguage::Hebrew => 'H',
+            StrongsLanguage::Greek => 'G',
+        };
+
+        write!(f, "{}{}", prefix, self.number)?;
+        if let Some(suffix) = self.suffix
+        {
+            write!(f, "{}", suffix)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for StrongsNumber
+{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        let s = s.trim();
+        let mut chars = s.chars();
+
+        let language = match chars.next().map(|c| c.to_ascii_uppercase())
+        {
+            Some('H') => StrongsLanguage::Hebrew,
+            Some('G') => StrongsLanguage::Greek,
+            _ => return Err(format!("Strong's number {} must start with `H` or `G`", s)),
+        };
+
+        let rest = chars.as_str();
+        let (digits, suffix) = match rest.char_indices().find(|(_, c)| !c.is_ascii_digit())
+        {
+            Some((i, _)) => (&rest[..i], &rest[i..]),
+            None => (rest, ""),
+        };
+
+        let number = digits.parse::<u32>().map_err(|_| format!("Strong's number {} has an invalid number", s))?;
+
+        let mut suffix_chars = suffix.chars();
+        let suffix = match (suffix_chars.next(), suffix_chars.next())
+        {
+            (None, _) => None,
+            (Some(c), None) if c.is_ascii_alphabetic() => Some(c.to_ascii_lowercase()),
+            _ => return Err(format!("Strong's number {} has an invalid suffix", s)),
+        };
+
+        Ok(Self { language, number, suffix })
+    }
+}
+
+impl Serialize for StrongsNumber
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for StrongsNumber
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        let s = String::deserialize(deserializer)?;
+        StrongsNumber::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct LexiconEntry
+{
+    pub strongs: StrongsNumber,
+    pub lemma: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transliteration: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pronunciation: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gloss: Option<String>,
+    pub definitions: Vec<String>,
+}
+
+impl LexiconEntry
+{
+    pub fn from_file(path: &str) -> Result<Vec<Self>, LoadError>
+    {
+        let ret = utils::load_json_lines(path)?
+            .into_iter()
+            .map(|(l, _)| l)
+            .collect();
+
+        Ok(ret)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LexiconModule
+{
+    pub id: String,
+    pub name: String,
+    pub version: Option<Version>,
+    pub authors: Vec<String>,
+    pub language: String,
+    pub description: Option<String>,
+    pub pub_year: Option<u32>,
+    pub license: Option<String>,
+    pub data_source: Option<String>,
+    entries: Vec<LexiconEntry>,
+    strongs_index: HashMap<StrongsNumber, usize>,
+    lemma_index: HashMap<String, Vec<usize>>,
+}
+
+impl LexiconModule
+{
+    pub fn load(dir_path: &str, name: &str) -> Result<Self, LoadError>
+    {
+        Self::load_with(dir_path, name, &LoadOptions::default()).map_err(utils::first_error)
+    }
+
+    pub fn load_with(dir_path: &str, name: &str, options: &LoadOptions) -> Result<Self, Vec<LoadError>>
+    {
+        let config_path = format!("{}/{}.toml", dir_path, name);
+        let lexicon_path = format!("{}/{}.jsonl", dir_path, name);
+
+        cache::load_cached(&config_path, &lexicon_path, options, || {
+            let config: LexiconConfig = utils::load_toml(&config_path).map_err(|e| vec![e])?;
+            let id = module_id(config.id.as_deref(), dir_path, name).map_err(|e| vec![e])?;
+
+            let mut errors = ErrorCollector::new(options.file_limit());
+            let entries: Vec<(LexiconEntry, usize)> = utils::load_json_lines_with(&lexicon_path, &mut errors);
+
+            let mut first_lines = HashMap::new();
+            for (entry, line) in entries.iter()
+            {
+                match first_lines.entry(&entry.strongs)
+                {
+                    Entry::Occupied(first) => errors.push(LoadError::config(&lexicon_path, Some(line + 1), format!("Strong's number {} is already defined on line {}", entry.strongs, first.get()))),
+                    Entry::Vacant(v) => { v.insert(line + 1); },
+                }
+            }
+
+            let entries = entries.into_iter().map(|(e, _)| e).collect();
+            let mut module = Self::new(config, entries);
+            module.id = id;
+
+            errors.finish(module)
+        })
+    }
+
+    /// If more than one entry has the same Strong's number, [`LexiconModule::find_strongs`] finds the first
+    pub fn new(config: LexiconConfig, entries: Vec<LexiconEntry>) -> Self
+    {
+        let mut strongs_index = HashMap::new();
+        let mut lemma_index = HashMap::<String, Vec<usize>>::new();
+
+        for (i, entry) in entries.iter().enumerate()
+        {
+            strongs_index.entry(entry.strongs.clone()).or_insert(i);
+            lemma_index.entry(normalize_lemma(&entry.lemma)).or_default().push(i);
+        }
+
+        Self {
+            id: config.id.unwrap_or_else(|| utils::file_stem(&config.name)),
+            name: config.name,
+            version: config.version,
+            authors: config.authors,
+            language: config.language,
+            description: config.description,
+            pub_year: config.pub_year,
+            license: config.license,
+            data_source: config.data_source,
+            entries,
+            strongs_index,
+            lemma_index,
+        }
+    }
+
+    pub fn config(&self) -> LexiconConfig
+    {
+        LexiconConfig {
+            id: Some(self.id.clone()),
+            name: self.name.clone(),
+            version: self.version.clone(),
+            authors: self.authors.clone(),
+            language: self.language.clone(),
+            description: self.description.clone(),
+            data_source: self.data_source.clone(),
+            pub_year: self.pub_year,
+            license: self.license.clone(),
+        }
+    }
+
+    /// Writes `{dir}/{name}.toml` and `{dir}/{name}.jsonl`
+    pub fn save(&self, dir_path: &str, name: &str) -> Result<(), LoadError>
+    {
+        ModuleConfig::Lexicon(self.config()).save(dir_path, name)?;
+        utils::write_json_lines(format!("{}/{}.jsonl", dir_path, name), &self.entries)
+    }
+
+    pub fn entries(&self) -> &[LexiconEntry]
+    {
+        &self.entries
+    }
+
+    pub fn find_strongs(&self, strongs: &StrongsNumber) -> Option<&LexiconEntry>
+    {
+        self.strongs_index.get(strongs).map(|i| &self.entries[*i])
+    }
+
+    /// Looks up an entry by a Strong's number string, e.g. `H430` or `G0025`
+    pub fn find_strongs_str(&self, strongs: &str) -> Option<&LexiconEntry>
+    {
+        self.find_strongs(&StrongsNumber::from_str(strongs).ok()?)
+    }
+
+    /// Finds all entries with the given lemma, ignoring vowel points, accents and breathing marks
+    pub fn find_lemma(&self, lemma: &str) -> Vec<&LexiconEntry>
+    {
+        self.lemma_index.get(&normalize_lemma(lemma))
+            .map(|e| e.iter().map(|i| &self.entries[*i]).collect())
+            .unwrap_or_default()
+    }
+}
+
+fn normalize_lemma(lemma: &str) -> String
+{
+    lemma.trim()
+        .nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+fn is_combining_mark(c: char) -> bool
+{
+    matches!(c, '\u{0300}'..='\u{036F}' | '\u{0591}'..='\u{05BD}' | '\u{05BF}' | '\u{05C1}'..='\u{05C2}' | '\u{05C4}'..='\u{05C5}' | '\u{05C7}')
+}
@@ -1,12 +1,148 @@
 pub mod bible;
 pub mod dict;
+pub mod lexicon;
 pub mod xrefs;
 
 use bible::BibleModule;
 
-use crate::modules::{dict::DictModule, xrefs::XRefModule};
+use serde::Serialize;
 
+use crate::{error::LoadError, modules::{bible::BibleConfig, dict::{DictConfig, DictModule}, lexicon::{LexiconConfig, LexiconModule}, xrefs::{XRefModule, XRefsConfig}}, utils, LoadOptions};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModuleKind
+{
+    Bible,
+    Dictionary,
+    XRef,
+    Lexicon,
+}
+
+impl ModuleKind
+{
+    /// Loads the module with config `{dir}/{name}.toml` and data `{dir}/{name}.jsonl`
+    pub fn load(self, dir_path: &str, name: &str, options: &LoadOptions) -> Result<Module, Vec<LoadError>>
+    {
+        Ok(match self
+        {
+            Self::Bible => Module::Bible(BibleModule::load_with(dir_path, name, options)?),
+            Self::Dictionary => Module::Dictionary(DictModule::load_with(dir_path, name, options)?),
+            Self::XRef => Module::XRef(XRefModule::load_with(dir_path, name, options)?),
+            Self::Lexicon => Module::Lexicon(LexiconModule::load_with(dir_path, name, options)?),
+        })
+    }
+
+    /// Loads only the config `{dir}/{name}.toml` of the module
+    pub fn load_config(self, dir_path: &str, name: &str) -> Result<ModuleConfig, LoadError>
+    {
+        let path = format!("{}/{}.toml", dir_path, name);
+        Ok(match self
+        {
+            Self::Bible => ModuleConfig::Bible(utils::load_toml(path)?),
+            Self::Dictionary => ModuleConfig::Dictionary(utils::load_toml(path)?),
+            Self::XRef => ModuleConfig::XRef(utils::load_toml(path)?),
+            Self::Lexicon => ModuleConfig::Lexicon(utils::load_toml(path)?),
+        })
+    }
+}
+
+/// The metadata of a module, without its data
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum ModuleConfig
+{
+    Bible(BibleConfig),
+    Dictionary(DictConfig),
+    XRef(XRefsConfig),
+    Lexicon(LexiconConfig),
+}
+
+impl ModuleConfig
+{
+    pub fn kind(&self) -> ModuleKind
+    {
+        match self
+        {
+            Self::Bible(_) => ModuleKind::Bible,
+            Self::Dictionary(_) => ModuleKind::Dictionary,
+            Self::XRef(_) => ModuleKind::XRef,
+            Self::Lexicon(_) => ModuleKind::Lexicon,
+        }
+    }
+
+    /// Identifies the module within its package. Defaults to the file stem of the config
+    pub fn id(&self) -> Option<&str>
+    {
+        match self
+        {
+            Self::Bible(b) => b.id.as_deref(),
+            Self::Dictionary(d) => d.id.as_deref(),
+            Self::XRef(x) => x.id.as_deref(),
+            Self::Lexicon(l) => l.id.as_deref(),
+        }
+    }
+
+    pub fn name(&self) -> &str
+    {
+        match self
+        {
+            Self::Bible(b) => &b.name,
+            Self::Dictionary(d) => &d.name,
+            Self::XRef(x) => &x.name,
+            Self::Lexicon(l) => &l.name,
+        }
+    }
+
+    /// Writes `{dir}/{name}.toml`, leaving out the id when it is implied by the file stem
+    pub fn save(mut self, dir_path: &str, name: &str) -> Result<(), LoadError>
+    {
+        let path = format!("{}/{}.toml", dir_path, name);
+        let id = match &mut self
+        {
+            Self::Bible(b) => &mut b.id,
+            Self::Dictionary(d) => &mut d.id,
+            Self::XRef(x) => &mut x.id,
+            Self::Lexicon(l) => &mut l.id,
+        };
+
+        if let Some(id) = id.as_deref()
+        {
+            validate_id(id).map_err(|e| LoadError::config(&path, None, e))?;
+        }
+
+        if id.as_deref() == Some(name)
+        {
+            *id = None;
+        }
+
+        utils::write_toml(path, &self)
+    }
+}
+
+/// The id of a module loaded from `{dir}/{name}.toml`, which is `name` unless the config sets one
+pub(crate) fn module_id(config_id: Option<&str>, dir_path: &str, name: &str) -> Result<String, LoadError>
+{
+    let id = config_id.unwrap_or(name);
+    validate_id(id).map_err(|e| LoadError::config(format!("{}/{}.toml", dir_path, name), None, e))?;
+    Ok(id.to_owned())
+}
+
+/// Module ids are used as file names when a package is saved, and after the `/` of a qualified `package/module` id
+pub fn validate_id(id: &str) -> Result<(), String>
+{
+    if id.is_empty()
+    {
+        Err("Module ids cannot be empty".into())
+    }
+    else if id.contains(['/', '\\']) || id == "." || id == ".."
+    {
+        Err(format!("Module id `{}` cannot contain `/` or `\\`, or be `.` or `..`", id))
+    }
+    else
+    {
+        Ok(())
+    }
+}
 
 #[derive(Debug)]
 pub enum Module
@@ -14,10 +150,55 @@ pub enum Module
     Bible(BibleModule),
     Dictionary(DictModule),
     XRef(XRefModule),
+    Lexicon(LexiconModule),
 }
 
 impl Module
 {
+    pub fn id(&self) -> &str
+    {
+        match self
+        {
+            Self::Bible(b) => &b.id,
+            Self::Dictionary(d) => &d.id,
+            Self::XRef(x) => &x.id,
+            Self::Lexicon(l) => &l.id,
+        }
+    }
+
+    pub fn name(&self) -> &str
+    {
+        match self
+        {
+            Self::Bible(b) => &b.name,
+            Self::Dictionary(d) => &d.name,
+            Self::XRef(x) => &x.name,
+            Self::Lexicon(l) => &l.name,
+        }
+    }
+
+    pub fn kind(&self) -> ModuleKind
+    {
+        match self
+        {
+            Self::Bible(_) => ModuleKind::Bible,
+            Self::Dictionary(_) => ModuleKind::Dictionary,
+            Self::XRef(_) => ModuleKind::XRef,
+            Self::Lexicon(_) => ModuleKind::Lexicon,
+        }
+    }
+
+    pub fn config(&self) -> ModuleConfig
+    {
+        match self
+        {
+            Self::Bible(b) => ModuleConfig::Bible(b.config()),
+            Self::Dictionary(d) => ModuleConfig::Dictionary(d.config()),
+            Self::XRef(x) => ModuleConfig::XRef(x.config()),
+            Self::Lexicon(l) => ModuleConfig::Lexicon(l.config()),
+        }
+    }
+
     pub fn is_bible(&self) -> bool
     {
         match self 
@@ -44,4 +225,13 @@ impl Module
             _ => false,
         }
     }
-}
+
+    pub fn is_lexicon(&self) -> bool
+    {
+        match self 
+        {
+            Self::Lexicon(_) => true,
+            _ => false,
+        }
+    }
+}
\ No newline at end of file
@@ -1,18 +1,26 @@
-use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-use crate::{ref_id::RefId, utils};
+use itertools::Itertools;
+use semver::Version;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{cache, citations, error::{ErrorCollector, LoadError}, modules::{module_id, ModuleConfig}, ref_id::{Atom, RefId}, utils, LoadOptions};
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct XRefsConfig
 {
+    pub id: Option<String>,
     pub name: String,
+    pub version: Option<Version>,
     pub description: Option<String>,
     pub data_source: Option<String>,
     pub license: Option<String>,
     pub language: Option<String>,
     pub pub_year: Option<u32>,
     pub bible_dep: Option<String>,
+    /// Versification mapping onto the `bible_dep`, which is not supported yet, so loading fails when it is set
+    pub versification: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -20,9 +28,66 @@ pub struct XRefsConfig
 pub struct MutualRef
 {
     pub id: RefId,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub text: Option<String>,
 }
 
+/// A target of a directed cross reference. Serialized as a plain reference string when it has no votes,
+/// otherwise as `{ "id": "Isa.53.5", "votes": 42 }`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct XRefTarget
+{
+    pub id: RefId,
+    /// Relevance votes, as given by e.g. the OpenBible.info cross references. May be negative
+    pub votes: Option<i32>,
+}
+
+impl From<RefId> for XRefTarget
+{
+    fn from(id: RefId) -> Self
+    {
+        Self { id, votes: None }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum XRefTargetRepr
+{
+    Id(RefId),
+    Weighted 
+    {
+        id: RefId,
+        votes: Option<i32>,
+    },
+}
+
+impl Serialize for XRefTarget
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        match self.votes
+        {
+            Some(votes) => XRefTargetRepr::Weighted { id: self.id.clone(), votes: Some(votes) }.serialize(serializer),
+            None => self.id.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for XRefTarget
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>
+    {
+        Ok(match XRefTargetRepr::deserialize(deserializer)?
+        {
+            XRefTargetRepr::Id(id) => Self { id, votes: None },
+            XRefTargetRepr::Weighted { id, votes } => Self { id, votes },
+        })
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum XRef 
@@ -30,20 +95,41 @@ pub enum XRef
     Directed 
     {
         source: RefId,
+        #[serde(skip_serializing_if = "Option::is_none")]
         source_text: Option<String>,
-        targets: Vec<RefId>,
+        targets: Vec<XRefTarget>,
+        #[serde(skip_serializing_if = "Option::is_none")]
         note: Option<String>,
     },
     Mutual 
     {
         refs: Vec<MutualRef>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        note: Option<String>,
+    },
+    /// A topical chain, like the Thompson chain references, which links a topic through an ordered sequence of references
+    Chain
+    {
+        topic: String,
+        refs: Vec<RefId>,
+        #[serde(skip_serializing_if = "Option::is_none")]
         note: Option<String>,
     },
 }
 
+/// The position of a reference inside of a [`XRef::Chain`]
+#[derive(Debug, Clone, Copy)]
+pub struct ChainLink<'a>
+{
+    pub topic: &'a str,
+    pub previous: Option<&'a RefId>,
+    pub current: &'a RefId,
+    pub next: Option<&'a RefId>,
+}
+
 impl XRef
 {
-    pub fn from_file(path: &str) -> Result<Vec<Self>, String>
+    pub fn from_file(path: &str) -> Result<Vec<Self>, LoadError>
     {
         let ret = utils::load_json_lines(path)?
             .into_iter()
@@ -58,15 +144,40 @@ impl XRef
         match self 
         {
             Self::Directed { source, source_text: _, targets: _, note: _ } => source == id,
-            Self::Mutual { refs, note: _ } => refs.iter().find(|r| r.id == *id).is_some()
+            Self::Mutual { refs, note: _ } => refs.iter().any(|r| r.id == *id),
+            Self::Chain { topic: _, refs, note: _ } => refs[..refs.len().saturating_sub(1)].contains(id),
         }
     }
+
+    /// The reference after `id` in a chain
+    pub fn chain_next(&self, id: &RefId) -> Option<&RefId>
+    {
+        let Self::Chain { topic: _, refs, note: _ } = self else {
+            return None;
+        };
+
+        let index = refs.iter().position(|r| r == id)?;
+        refs.get(index + 1)
+    }
+
+    /// The reference before `id` in a chain
+    pub fn chain_previous(&self, id: &RefId) -> Option<&RefId>
+    {
+        let Self::Chain { topic: _, refs, note: _ } = self else {
+            return None;
+        };
+
+        let index = refs.iter().position(|r| r == id)?;
+        refs.get(index.checked_sub(1)?)
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct XRefModule
 {
+    pub id: String,
     pub name: String,
+    pub version: Option<Version>,
     pub description: Option<String>,
     pub data_source: Option<String>,
     pub pub_year: Option<u32>,
@@ -74,27 +185,349 @@ pub struct XRefModule
     pub license: Option<String>,
     pub refs: Vec<XRef>,
     pub bible_dep: Option<String>,
+    /// Path of the json lines file the refs were loaded from
+    #[serde(skip)]
+    pub path: String,
+    lines: Vec<usize>,
+    index: XRefIndex,
 }
 
 impl XRefModule
 {
-    pub fn load(dir_path: &str, name: &str) -> Result<Self, String>
+    pub fn load(dir_path: &str, name: &str) -> Result<Self, LoadError>
+    {
+        Self::load_with(dir_path, name, &LoadOptions::default()).map_err(utils::first_error)
+    }
+
+    pub fn load_with(dir_path: &str, name: &str, options: &LoadOptions) -> Result<Self, Vec<LoadError>>
     {
         let config_path = format!("{}/{}.toml", dir_path, name);
-        let config: XRefsConfig = utils::load_toml(config_path)?;
+        let xrefs_path = format!("{}/{}.jsonl", dir_path, name);
+
+        let mut module = cache::load_cached(&config_path, &xrefs_path, options, || {
+            let config: XRefsConfig = utils::load_toml(&config_path).map_err(|e| vec![e])?;
+            let id = module_id(config.id.as_deref(), dir_path, name).map_err(|e| vec![e])?;
+
+            if let Some(versification) = &config.versification
+            {
+                let message = format!("Versification mapping ({}) is not supported, references must use the versification of the `bible_dep`", versification);
+                return Err(vec![LoadError::config(&config_path, None, message)]);
+            }
+
+            let mut errors = ErrorCollector::new(options.file_limit());
+            let (refs, lines) = utils::load_json_lines_with(&xrefs_path, &mut errors).into_iter().unzip();
+
+            let mut module = Self::new(config, refs);
+            module.id = id;
+            module.lines = lines;
 
-        let dictionary_path = format!("{}/{}.jsonl", dir_path, name);
-        let refs = XRef::from_file(&dictionary_path)?;
+            errors.finish(module)
+        })?;
 
-        Ok(Self { 
+        // not cached, as the package may have moved since the cache was made
+        module.path = xrefs_path;
+        Ok(module)
+    }
+
+    /// Cross references that were not loaded from a file, so [`XRefModule::path`] is empty
+    pub fn new(config: XRefsConfig, refs: Vec<XRef>) -> Self
+    {
+        Self { 
+            id: config.id.unwrap_or_else(|| utils::file_stem(&config.name)),
             name: config.name,
+            version: config.version,
             description: config.description,
             language: config.language,
             pub_year: config.pub_year,
             license: config.license,
             data_source: config.data_source,
             bible_dep: config.bible_dep,
+            path: String::new(),
+            lines: vec![],
+            index: XRefIndex::new(&refs),
             refs,
-        })
+        }
+    }
+
+    pub fn config(&self) -> XRefsConfig
+    {
+        XRefsConfig {
+            id: Some(self.id.clone()),
+            name: self.name.clone(),
+            version: self.version.clone(),
+            description: self.description.clone(),
+            data_source: self.data_source.clone(),
+            license: self.license.clone(),
+            language: self.language.clone(),
+            pub_year: self.pub_year,
+            bible_dep: self.bible_dep.clone(),
+            versification: None,
+        }
+    }
+
+    /// Writes `{dir}/{name}.toml` and `{dir}/{name}.jsonl`
+    pub fn save(&self, dir_path: &str, name: &str) -> Result<(), LoadError>
+    {
+        ModuleConfig::XRef(self.config()).save(dir_path, name)?;
+        utils::write_json_lines(format!("{}/{}.jsonl", dir_path, name), &self.refs)
+    }
+
+    /// The 1 based line number of the ref at `index` in [`XRefModule::path`]
+    pub fn line(&self, index: usize) -> usize
+    {
+        self.lines.get(index).map_or(index, |l| *l) + 1
+    }
+
+    /// Rebuilds the verse index, must be called after [`XRefModule::refs`] is modified
+    pub fn reindex(&mut self)
+    {
+        self.index = XRefIndex::new(&self.refs);
+    }
+
+    /// All cross references that reference the verse, either as a source, a target or as part of a mutual reference
+    pub fn refs_touching(&self, verse: &RefId) -> Vec<&XRef>
+    {
+        let Some((book, chapter, verse)) = verse_key(verse) else {
+            return vec![];
+        };
+
+        self.index.sources.get(book, chapter, verse)
+            .merge(self.index.targets.get(book, chapter, verse))
+            .dedup()
+            .map(|i| &self.refs[i])
+            .collect()
     }
-}
\ No newline at end of file
+
+    /// All cross references going out from the verse, i.e. where it is a source, or part of a mutual reference
+    pub fn refs_from(&self, verse: &RefId) -> Vec<&XRef>
+    {
+        let Some((book, chapter, verse)) = verse_key(verse) else {
+            return vec![];
+        };
+
+        self.index.sources.get(book, chapter, verse)
+            .map(|i| &self.refs[i])
+            .collect()
+    }
+
+    /// All cross references pointing to the verse, i.e. where it is a target, or part of a mutual reference
+    pub fn refs_to(&self, verse: &RefId) -> Vec<&XRef>
+    {
+        let Some((book, chapter, verse)) = verse_key(verse) else {
+            return vec![];
+        };
+
+        self.index.targets.get(book, chapter, verse)
+            .map(|i| &self.refs[i])
+            .collect()
+    }
+
+    /// The targets of all directed cross references from the verse, sorted by votes, highest first. 
+    /// If `min_votes` is given, targets with fewer or no votes are skipped
+    pub fn weighted_targets(&self, verse: &RefId, min_votes: Option<i32>) -> Vec<&XRefTarget>
+    {
+        self.refs_from(verse).into_iter()
+            .filter_map(|r| match r {
+                XRef::Directed { source: _, source_text: _, targets, note: _ } => Some(targets),
+                XRef::Mutual { refs: _, note: _ } => None,
+                XRef::Chain { topic: _, refs: _, note: _ } => None,
+            })
+            .flatten()
+            .filter(|t| min_votes.is_none_or(|min| t.votes.is_some_and(|v| v >= min)))
+            .sorted_by_key(|t| std::cmp::Reverse(t.votes.map_or(i64::MIN, i64::from)))
+            .collect()
+    }
+
+    /// The references that point to the verse, e.g. which verses point to `Isa.53.5`
+    pub fn sources_pointing_to(&self, verse: &RefId) -> Vec<&RefId>
+    {
+        let Some((book, chapter, verse_idx)) = verse_key(verse) else {
+            return vec![];
+        };
+
+        self.index.targets.get(book, chapter, verse_idx)
+            .flat_map(|i| match &self.refs[i] {
+                XRef::Directed { source, source_text: _, targets: _, note: _ } => vec![source],
+                XRef::Mutual { refs, note: _ } => refs.iter()
+                    .map(|r| &r.id)
+                    .filter(|id| !ref_contains(id, book, chapter, verse_idx))
+                    .collect(),
+                XRef::Chain { topic: _, refs, note: _ } => refs.iter()
+                    .tuple_windows()
+                    .filter(|(_, next)| ref_contains(next, book, chapter, verse_idx))
+                    .map(|(previous, _)| previous)
+                    .collect(),
+            })
+            .unique()
+            .collect()
+    }
+
+    /// Every chain the verse is part of, with the references before and after it
+    pub fn chain_links(&self, verse: &RefId) -> Vec<ChainLink<'_>>
+    {
+        let Some((book, chapter, verse_idx)) = verse_key(verse) else {
+            return vec![];
+        };
+
+        self.refs_touching(verse).into_iter()
+            .filter_map(|r| match r {
+                XRef::Chain { topic, refs, note: _ } => Some((topic, refs)),
+                _ => None,
+            })
+            .flat_map(|(topic, refs)| refs.iter().enumerate()
+                .filter(|(_, r)| ref_contains(r, book, chapter, verse_idx))
+                .map(|(i, current)| ChainLink {
+                    topic,
+                    previous: i.checked_sub(1).map(|p| &refs[p]),
+                    current,
+                    next: refs.get(i + 1),
+                })
+                .collect_vec()
+            )
+            .collect()
+    }
+}
+
+type Span = ((u32, u32), (u32, u32));
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct VerseMap
+{
+    verses: HashMap<(String, u32, u32), Vec<usize>>,
+    // references that cannot be cheaply expanded to single verses, like whole chapters, or ranges across chapters
+    spans: HashMap<String, Vec<(Span, usize)>>,
+}
+
+impl VerseMap
+{
+    fn insert(&mut self, id: &RefId, xref: usize)
+    {
+        for (book, span) in ref_spans(id)
+        {
+            let ((start_chapter, start_verse), (end_chapter, end_verse)) = span;
+            if start_chapter == end_chapter && end_verse != u32::MAX
+            {
+                for verse in start_verse..=end_verse
+                {
+                    self.verses.entry((book.to_owned(), start_chapter, verse)).or_default().push(xref);
+                }
+            }
+            else
+            {
+                self.spans.entry(book.to_owned()).or_default().push((span, xref));
+            }
+        }
+    }
+
+    /// The references covering the verse, in order. Both lists are filled in reference order, so they are merged without sorting
+    fn get<'a>(&'a self, book: &'a str, chapter: u32, verse: u32) -> impl Iterator<Item = usize> + 'a
+    {
+        let verses = self.verses.get(&(book.to_owned(), chapter, verse)).into_iter().flatten();
+        let spans = self.spans.get(book).into_iter().flatten()
+            .filter(move |(span, _)| span_contains(span, chapter, verse))
+            .map(|(_, i)| i);
+
+        verses.merge(spans).copied().dedup()
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct XRefIndex
+{
+    sources: VerseMap,
+    targets: VerseMap,
+}
+
+impl XRefIndex
+{
+    fn new(refs: &[XRef]) -> Self
+    {
+        let mut index = Self::default();
+        for (i, xref) in refs.iter().enumerate()
+        {
+            match xref
+            {
+                XRef::Directed { source, source_text: _, targets, note: _ } => {
+                    index.sources.insert(source, i);
+                    targets.iter().for_each(|t| index.targets.insert(&t.id, i));
+                },
+                XRef::Mutual { refs, note: _ } => refs.iter().for_each(|r| {
+                    index.sources.insert(&r.id, i);
+                    index.targets.insert(&r.id, i);
+                }),
+                XRef::Chain { topic: _, refs, note: _ } => {
+                    refs.iter().rev().skip(1).for_each(|r| index.sources.insert(r, i));
+                    refs.iter().skip(1).for_each(|r| index.targets.insert(r, i));
+                },
+            }
+        }
+
+        index
+    }
+}
+
+fn verse_key(id: &RefId) -> Option<(&str, u32, u32)>
+{
+    let RefId::Single(atom) = id else {
+        return None;
+    };
+
+    Some((atom.book(), atom.chapter()?.get(), atom.verse()?.get()))
+}
+
+fn atom_span(atom: &Atom) -> Span
+{
+    match (atom.chapter(), atom.verse())
+    {
+        (Some(chapter), Some(verse)) => ((chapter.get(), verse.get()), (chapter.get(), verse.get())),
+        (Some(chapter), None) => ((chapter.get(), 1), (chapter.get(), u32::MAX)),
+        _ => ((1, 1), (u32::MAX, u32::MAX)),
+    }
+}
+
+fn span_contains(((start_chapter, start_verse), (end_chapter, end_verse)): &Span, chapter: u32, verse: u32) -> bool
+{
+    (*start_chapter, *start_verse) <= (chapter, verse) && (chapter, verse) <= (*end_chapter, *end_verse)
+}
+
+pub(crate) fn ref_contains(id: &RefId, book: &str, chapter: u32, verse: u32) -> bool
+{
+    ref_spans(id).iter().any(|(b, span)| *b == book && span_contains(span, chapter, verse))
+}
+
+/// The span of a reference in each book it covers. Ranges across books cover every book in between,
+/// except for books outside the canon, which cannot be ordered, so only the ends of the range are known
+fn ref_spans(id: &RefId) -> Vec<(&str, Span)>
+{
+    let (from, to) = match id
+    {
+        RefId::Single(atom) => return vec![(atom.book(), atom_span(atom))],
+        RefId::Range { from, to } if from.book() == to.book() => return vec![(from.book(), (atom_span(from).0, atom_span(to).1))],
+        RefId::Range { from, to } => (from, to),
+    };
+
+    let Some(books) = citations::books_between(from.book(), to.book()) else {
+        return vec![(from.book(), atom_span(from)), (to.book(), atom_span(to))];
+    };
+
+    books.map(|book| {
+        let start = if book == from.book() { atom_span(from).0 } else { (1, 1) };
+        let end = if book == to.book() { atom_span(to).1 } else { (u32::MAX, u32::MAX) };
+        (book, (start, end))
+    }).collect()
+}
+
+/// Whether `outer` covers every verse of `inner`
+pub(crate) fn ref_covers(outer: &RefId, inner: &RefId) -> bool
+{
+    let (from, to) = match inner
+    {
+        RefId::Single(atom) => (atom, atom),
+        RefId::Range { from, to } => (from, to),
+    };
+
+    let (from_start, _) = atom_span(from);
+    let (_, to_end) = atom_span(to);
+
+    ref_contains(outer, from.book(), from_start.0, from_start.1) && ref_contains(outer, to.book(), to_end.0, to_end.1)
+}
@@ -1,57 +1,174 @@
-use std::{fs, path::Path};
+use std::{fs, path::{Path, PathBuf}};
 
 use itertools::Itertools;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-pub fn load_file<P>(path: P) -> Result<String, String>
+use crate::error::{ErrorCollector, LoadError};
+
+pub fn load_file<P>(path: P) -> Result<String, LoadError>
     where P : AsRef<Path>
 {
-    match fs::read(path)
+    let display = path.as_ref().display();
+    match fs::read(path.as_ref())
     {
-        Ok(ok) => match String::from_utf8(ok)
-        {
-            Ok(ok) => Ok(ok),
-            Err(err) => return Err(err.to_string()),
-        }
-        Err(err) => return Err(err.to_string())
+        Ok(ok) => String::from_utf8(ok).map_err(|e| LoadError::Io { 
+            path: display.to_string(), 
+            kind: std::io::ErrorKind::InvalidData, 
+            message: e.to_string() 
+        }),
+        Err(err) => Err(LoadError::io(display, &err))
     }
 }
 
-pub fn load_toml<T, P>(path: P) -> Result<T, String>
+pub fn load_toml<T, P>(path: P) -> Result<T, LoadError>
     where P : AsRef<Path>,
           T : for<'a> Deserialize<'a>
 {
-    let src = load_file(path)?;
+    let src = load_file(path.as_ref())?;
     toml::from_str(&src)
-        .map_err(|e| e.to_string())
+        .map_err(|e| LoadError::toml(path.as_ref().display(), &src, &e))
 }
 
-pub fn load_json<T, P>(path: P) -> Result<T, String> 
+pub fn load_json_lines<T, P>(path: P) -> Result<Vec<(T, usize)>, LoadError>
     where P : AsRef<Path>,
-          T : for<'a> Deserialize<'a>
+          T : for<'a> Deserialize<'a> + Send + Sync + 'static
 {
-    let src = load_file(path)?;
-    serde_json::from_str(&src)
-        .map_err(|e| e.to_string())
+    let src = load_file(path.as_ref())?;
+    let display = path.as_ref().display().to_string();
+
+    let results = src.lines().enumerate().filter(|(_, v)| !v.is_empty()).collect_vec().into_par_iter().map(|(line, json)| {
+        match serde_json::from_str::<T>(json)
+        {
+            Ok(ok) => Ok((ok, line)),
+            Err(e) => Err(LoadError::json_line(&display, line + 1, &e))
+        }
+    }).collect::<Vec<_>>();
+
+    // collected in line order first, as collecting into a `Result` stops at whichever error a thread finds first
+    results.into_iter().collect()
 }
 
-pub fn load_json_lines<T, P>(path: P) -> Result<Vec<(T, usize)>, String>
+/// Like [`load_json_lines`], but keeps going past malformed lines, adding an error for each one to `errors`
+pub fn load_json_lines_with<T, P>(path: P, errors: &mut ErrorCollector) -> Vec<(T, usize)>
     where P : AsRef<Path>,
           T : for<'a> Deserialize<'a> + Send + Sync + 'static
 {
-    let src = load_file(path)?;
-    src.lines().enumerate().filter(|(_, v)| !v.is_empty()).collect_vec().into_par_iter().map(|(line, json)| {
-        match serde_json::from_str::<T>(json)
+    let src = match load_file(path.as_ref()) {
+        Ok(ok) => ok,
+        Err(e) => {
+            errors.push(e);
+            return vec![];
+        }
+    };
+
+    parse_json_lines_with(&src, &path.as_ref().display().to_string(), errors)
+}
+
+/// Parses json lines already read from the file at `display`, adding an error for each malformed line to `errors`
+pub fn parse_json_lines_with<T>(src: &str, display: &str, errors: &mut ErrorCollector) -> Vec<(T, usize)>
+    where T : for<'a> Deserialize<'a> + Send + Sync + 'static
+{
+    let results = src.lines().enumerate().filter(|(_, v)| !v.is_empty()).collect_vec().into_par_iter().map(|(line, json)| {
+        serde_json::from_str::<T>(json)
+            .map(|ok| (ok, line))
+            .map_err(|e| LoadError::json_line(display, line + 1, &e))
+    }).collect::<Vec<_>>();
+
+    let mut values = vec![];
+    for result in results
+    {
+        match result
         {
-            Ok(ok) => Ok((ok, line)),
-            Err(e) => Err(e.to_string())
+            Ok(ok) => values.push(ok),
+            Err(e) => errors.push(e),
         }
-    }).collect()
+    }
+
+    values
+}
+
+/// The first of a non empty list of errors, for the single error versions of the `load_with` functions
+pub fn first_error(errors: Vec<LoadError>) -> LoadError
+{
+    errors.into_iter().next().expect("Expected at least one error")
 }
 
-pub fn write_file<P>(path: P, src: &str) -> Result<(), String>
+/// Writes `src` to `path`, creating any missing parent directories
+pub fn write_file<P>(path: P, src: &str) -> Result<(), LoadError>
     where P : AsRef<Path>
 {
-    fs::write(path, src).map_err(|e| e.to_string())
-}
\ No newline at end of file
+    if let Some(parent) = path.as_ref().parent().filter(|p| !p.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent).map_err(|e| LoadError::io(parent.display(), &e))?;
+    }
+
+    fs::write(path.as_ref(), src).map_err(|e| LoadError::io(path.as_ref().display(), &e))
+}
+
+pub fn write_toml<T, P>(path: P, value: &T) -> Result<(), LoadError>
+    where P : AsRef<Path>,
+          T : Serialize
+{
+    let src = toml::to_string(value).map_err(|e| LoadError::write(path.as_ref().display(), e))?;
+    write_file(path, &src)
+}
+
+pub fn write_json_lines<'a, T, P>(path: P, values: impl IntoIterator<Item = &'a T>) -> Result<(), LoadError>
+    where P : AsRef<Path>,
+          T : Serialize + 'a
+{
+    let mut src = String::new();
+    for value in values
+    {
+        let json = serde_json::to_string(value).map_err(|e| LoadError::write(path.as_ref().display(), e))?;
+        src.push_str(&json);
+        src.push('\n');
+    }
+
+    write_file(path, &src)
+}
+
+/// A file stem for a module name, e.g. `TSK Cross References` becomes `tsk_cross_references`
+pub fn file_stem(name: &str) -> String
+{
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_lowercase())
+        .join("_")
+}
+
+/// Every file under `root`, relative to it and sorted
+pub fn package_files(root: &Path) -> Result<Vec<PathBuf>, LoadError>
+{
+    let mut files = vec![];
+    let mut stack = vec![PathBuf::new()];
+
+    while let Some(relative) = stack.pop()
+    {
+        let dir = root.join(&relative);
+        for entry in fs::read_dir(&dir).map_err(|e| LoadError::io(dir.display(), &e))?
+        {
+            let entry = entry.map_err(|e| LoadError::io(dir.display(), &e))?;
+            let path = relative.join(entry.file_name());
+
+            if entry.file_type().map_err(|e| LoadError::io(entry.path().display(), &e))?.is_dir()
+            {
+                stack.push(path);
+            }
+            else
+            {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// A relative path with `/` separators, whatever the platform
+pub fn slash_path(path: &Path) -> String
+{
+    path.components().map(|c| c.as_os_str().to_string_lossy()).join("/")
+}
@@ -0,0 +1,416 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use itertools::Itertools;
+
+use crate::{citations, modules::xrefs::{ref_covers, XRef, XRefModule}, ref_id::RefId, xref_merge::MergedXRefs};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DegreeStats
+{
+    pub nodes: usize,
+    pub edges: usize,
+    pub max_in_degree: usize,
+    pub max_out_degree: usize,
+    pub mean_degree: f64,
+}
+
+/// Number of links between books, `counts[from][to]` indexes into `books`
+#[derive(Debug, Clone)]
+pub struct BookMatrix
+{
+    pub books: Vec<String>,
+    pub counts: Vec<Vec<usize>>,
+}
+
+/// A directed graph over the references of one or more cross reference modules. Mutual references are
+/// added as edges in both directions. Every distinct [`RefId`] is a node, and ranges, chapters and books are connected
+/// to the nodes inside them, so links to a range also reach the verses it covers.
+#[derive(Debug, Default)]
+pub struct XRefGraph
+{
+    nodes: Vec<RefId>,
+    node_indices: HashMap<RefId, usize>,
+    out_edges: Vec<Vec<usize>>,
+    in_edges: Vec<Vec<usize>>,
+    /// The nodes covered by each range, chapter or book node. Not counted as edges
+    covers: Vec<Vec<usize>>,
+    covered_by: Vec<Vec<usize>>,
+}
+
+impl XRefGraph
+{
+    pub fn from_module(module: &XRefModule) -> Self
+    {
+        let mut graph = Self::default();
+        for xref in module.refs.iter()
+        {
+            match xref
+            {
+                XRef::Directed { source, source_text: _, targets, note: _ } => {
+                    targets.iter().for_each(|t| graph.add_edge(source, &t.id));
+                },
+                XRef::Mutual { refs, note: _ } => {
+                    for (a, b) in refs.iter().tuple_combinations()
+                    {
+                        graph.add_edge(&a.id, &b.id);
+                        graph.add_edge(&b.id, &a.id);
+                    }
+                },
+                XRef::Chain { topic: _, refs, note: _ } => {
+                    refs.iter().tuple_windows().for_each(|(a, b)| graph.add_edge(a, b));
+                },
+            }
+        }
+
+        graph.link_ranges();
+        graph
+    }
+
+    pub fn from_merged(merged: &MergedXRefs) -> Self
+    {
+        let mut graph = Self::default();
+        for link in merged.links.iter()
+        {
+            graph.add_edge(&link.from, &link.to);
+            if link.mutual
+            {
+                graph.add_edge(&link.to, &link.from);
+            }
+        }
+
+        for chain in merged.chains.iter()
+        {
+            chain.refs.iter().tuple_windows().for_each(|(a, b)| graph.add_edge(a, b));
+        }
+
+        graph.link_ranges();
+        graph
+    }
+
+    pub fn nodes(&self) -> &[RefId]
+    {
+        &self.nodes
+    }
+
+    pub fn edge_count(&self) -> usize
+    {
+        self.out_edges.iter().map(|e| e.len()).sum()
+    }
+
+    pub fn in_degree(&self, id: &RefId) -> usize
+    {
+        self.node_indices.get(id).map_or(0, |i| self.in_edges[*i].len())
+    }
+
+    pub fn out_degree(&self, id: &RefId) -> usize
+    {
+        self.node_indices.get(id).map_or(0, |i| self.out_edges[*i].len())
+    }
+
+    pub fn degree_stats(&self) -> DegreeStats
+    {
+        let nodes = self.nodes.len();
+        let edges = self.edge_count();
+
+        DegreeStats {
+            nodes,
+            edges,
+            max_in_degree: self.in_edges.iter().map(|e| e.len()).max().unwrap_or(0),
+            max_out_degree: self.out_edges.iter().map(|e| e.len()).max().unwrap_or(0),
+            mean_degree: if nodes == 0 { 0.0 } else { (2 * edges) as f64 / nodes as f64 },
+        }
+    }
+
+    /// The `count` references with the most incoming links, counting links to the ranges, chapters and books that cover them,
+    /// with ties in canonical order
+    pub fn most_referenced(&self, count: usize) -> Vec<(&RefId, usize)>
+    {
+        let counts = (0..self.nodes.len()).map(|node| {
+            let sources = self.in_edges[node].iter()
+                .chain(self.covered_by[node].iter().flat_map(|r| self.in_edges[*r].iter()))
+                .filter(|s| **s != node)
+                .collect::<HashSet<_>>();
+
+            sources.len()
+        });
+
+        self.nodes.iter()
+            .zip(counts)
+            .sorted_by(|(a_id, a), (b_id, b)| b.cmp(a).then_with(|| compare_refs(a_id, b_id)))
+            .take(count)
+            .collect()
+    }
+
+    /// The `count` books with the most incoming links
+    pub fn most_referenced_books(&self, count: usize) -> Vec<(String, usize)>
+    {
+        let mut books = HashMap::<&str, usize>::new();
+        for (node, edges) in self.nodes.iter().zip(self.in_edges.iter())
+        {
+            *books.entry(node_book(node)).or_default() += edges.len();
+        }
+
+        books.into_iter()
+            .sorted_by(|(a_book, a), (b_book, b)| b.cmp(a).then_with(|| compare_books(a_book, b_book)))
+            .take(count)
+            .map(|(book, count)| (book.to_owned(), count))
+            .collect()
+    }
+
+    /// The shortest chain of links leading from `from` to `to`, including both ends. A range in the chain may be followed by a verse it covers
+    pub fn shortest_chain(&self, from: &RefId, to: &RefId) -> Option<Vec<&RefId>>
+    {
+        let start = *self.node_indices.get(from)?;
+        let end = *self.node_indices.get(to)?;
+
+        let mut previous = HashMap::<usize, usize>::new();
+        let mut visited = HashSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(node) = queue.pop_front()
+        {
+            if node == end
+            {
+                let mut chain = vec![&self.nodes[end]];
+                let mut current = end;
+                while let Some(prev) = previous.get(&current)
+                {
+                    chain.push(&self.nodes[*prev]);
+                    current = *prev;
+                }
+
+                chain.reverse();
+                return Some(chain);
+            }
+
+            for next in self.out_edges[node].iter().chain(self.covers[node].iter())
+            {
+                if visited.insert(*next)
+                {
+                    previous.insert(*next, node);
+                    queue.push_back(*next);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// The weakly connected components, largest first. As with [`XRefGraph::shortest_chain`], a range, chapter or book is connected to the references inside it
+    pub fn connected_components(&self) -> Vec<Vec<&RefId>>
+    {
+        let mut component = vec![usize::MAX; self.nodes.len()];
+        let mut components = vec![];
+
+        for start in 0..self.nodes.len()
+        {
+            if component[start] != usize::MAX
+            {
+                continue;
+            }
+
+            let id = components.len();
+            let mut members = vec![];
+            let mut stack = vec![start];
+            component[start] = id;
+
+            while let Some(node) = stack.pop()
+            {
+                members.push(&self.nodes[node]);
+                let neighbours = self.out_edges[node].iter()
+                    .chain(self.in_edges[node].iter())
+                    .chain(self.covers[node].iter())
+                    .chain(self.covered_by[node].iter());
+
+                for next in neighbours
+                {
+                    if component[*next] == usize::MAX
+                    {
+                        component[*next] = id;
+                        stack.push(*next);
+                    }
+                }
+            }
+
+            components.push(members);
+        }
+
+        components.sort_by_key(|c| std::cmp::Reverse(c.len()));
+        components
+    }
+
+    pub fn book_adjacency(&self) -> BookMatrix
+    {
+        let books = self.nodes.iter()
+            .map(node_book)
+            .unique()
+            .sorted_by(|a, b| compare_books(a, b))
+            .collect_vec();
+
+        let book_indices = books.iter().enumerate().map(|(i, b)| (*b, i)).collect::<HashMap<_, _>>();
+        let mut counts = vec![vec![0; books.len()]; books.len()];
+
+        for (from, edges) in self.out_edges.iter().enumerate()
+        {
+            let from_book = book_indices[node_book(&self.nodes[from])];
+            for to in edges
+            {
+                counts[from_book][book_indices[node_book(&self.nodes[*to])]] += 1;
+            }
+        }
+
+        BookMatrix {
+            books: books.into_iter().map(|b| b.to_owned()).collect(),
+            counts
+        }
+    }
+
+    /// Exports the graph in the Graphviz DOT format
+    pub fn to_dot(&self) -> String
+    {
+        let mut out = String::from("digraph xrefs {\n");
+        for (i, node) in self.nodes.iter().enumerate()
+        {
+            out.push_str(&format!("    n{} [label=\"{}\"];\n", i, node));
+        }
+
+        for (from, edges) in self.out_edges.iter().enumerate()
+        {
+            for to in edges
+            {
+                out.push_str(&format!("    n{} -> n{};\n", from, to));
+            }
+        }
+
+        out.push_str("}\n");
+        out
+    }
+
+    pub fn to_graphml(&self) -> String
+    {
+        let mut out = String::new();
+        out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        out.push_str("  <key id=\"ref\" for=\"node\" attr.name=\"ref\" attr.type=\"string\"/>\n");
+        out.push_str("  <key id=\"book\" for=\"node\" attr.name=\"book\" attr.type=\"string\"/>\n");
+        out.push_str("  <graph id=\"xrefs\" edgedefault=\"directed\">\n");
+
+        for (i, node) in self.nodes.iter().enumerate()
+        {
+            out.push_str(&format!(
+                "    <node id=\"n{}\"><data key=\"ref\">{}</data><data key=\"book\">{}</data></node>\n",
+                i, escape_xml(&node.to_string()), escape_xml(node_book(node))
+            ));
+        }
+
+        for (from, edges) in self.out_edges.iter().enumerate()
+        {
+            for to in edges
+            {
+                out.push_str(&format!("    <edge source=\"n{}\" target=\"n{}\"/>\n", from, to));
+            }
+        }
+
+        out.push_str("  </graph>\n");
+        out.push_str("</graphml>\n");
+        out
+    }
+
+    fn add_edge(&mut self, from: &RefId, to: &RefId)
+    {
+        let from = self.node(from);
+        let to = self.node(to);
+
+        if from != to && !self.out_edges[from].contains(&to)
+        {
+            self.out_edges[from].push(to);
+            self.in_edges[to].push(from);
+        }
+    }
+
+    /// Connects every range, chapter and book node to the nodes it covers, once all edges are added
+    fn link_ranges(&mut self)
+    {
+        let mut by_book = HashMap::<&str, Vec<usize>>::new();
+        for (i, node) in self.nodes.iter().enumerate()
+        {
+            by_book.entry(node_book(node)).or_default().push(i);
+        }
+
+        for (outer, node) in self.nodes.iter().enumerate().filter(|(_, n)| !is_verse(n))
+        {
+            let books = match node {
+                RefId::Range { from, to } => vec![from.book(), to.book()],
+                RefId::Single(atom) => vec![atom.book()],
+            };
+
+            for inner in books.into_iter().unique().filter_map(|b| by_book.get(b)).flatten()
+            {
+                if *inner != outer && ref_covers(node, &self.nodes[*inner])
+                {
+                    self.covers[outer].push(*inner);
+                    self.covered_by[*inner].push(outer);
+                }
+            }
+        }
+    }
+
+    fn node(&mut self, id: &RefId) -> usize
+    {
+        if let Some(i) = self.node_indices.get(id)
+        {
+            return *i;
+        }
+
+        self.nodes.push(id.clone());
+        self.out_edges.push(vec![]);
+        self.in_edges.push(vec![]);
+        self.covers.push(vec![]);
+        self.covered_by.push(vec![]);
+        self.node_indices.insert(id.clone(), self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+}
+
+fn node_book(id: &RefId) -> &str
+{
+    match id
+    {
+        RefId::Single(atom) => atom.book(),
+        RefId::Range { from, to: _ } => from.book(),
+    }
+}
+
+fn is_verse(id: &RefId) -> bool
+{
+    matches!(id, RefId::Single(atom) if atom.verse().is_some())
+}
+
+/// Orders by book, then by the first chapter and verse
+fn compare_refs(a: &RefId, b: &RefId) -> std::cmp::Ordering
+{
+    let start = |id: &RefId| {
+        let atom = match id {
+            RefId::Single(atom) => atom,
+            RefId::Range { from, to: _ } => from,
+        };
+
+        (atom.chapter().map_or(0, |c| c.get()), atom.verse().map_or(0, |v| v.get()))
+    };
+
+    compare_books(node_book(a), node_book(b)).then_with(|| start(a).cmp(&start(b)))
+}
+
+fn compare_books(a: &str, b: &str) -> std::cmp::Ordering
+{
+    let order = |book: &str| citations::book_order(book).unwrap_or(usize::MAX);
+    order(a).cmp(&order(b)).then_with(|| a.cmp(b))
+}
+
+fn escape_xml(text: &str) -> String
+{
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
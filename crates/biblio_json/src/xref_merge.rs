@@ -0,0 +1,272 @@
+use std::collections::{BTreeSet, HashMap};
+
+use itertools::Itertools;
+
+use crate::{error::LoadError, modules::xrefs::{ref_covers, MutualRef, XRef, XRefModule, XRefTarget, XRefsConfig}, ref_id::RefId};
+
+/// A single deduplicated link between two references
+#[derive(Debug, Clone)]
+pub struct MergedLink
+{
+    pub from: RefId,
+    pub to: RefId,
+    /// Whether the link goes both ways, either because it came from a mutual reference, or because both directions were given
+    pub mutual: bool,
+    /// The highest number of votes any of the merged modules gave this link
+    pub votes: Option<i32>,
+    /// The text of `from`, from the `source_text` of a directed reference or the `text` of a mutual reference
+    pub from_text: Option<String>,
+    /// The text of `to`, from the `text` of a mutual reference
+    pub to_text: Option<String>,
+    /// The notes of every reference this link was merged from
+    pub notes: BTreeSet<String>,
+    /// The names of the modules this link came from
+    pub provenance: BTreeSet<String>,
+}
+
+/// A topical chain, kept whole rather than split into links so its topic and order are not lost
+#[derive(Debug, Clone)]
+pub struct MergedChain
+{
+    pub topic: String,
+    pub refs: Vec<RefId>,
+    pub notes: BTreeSet<String>,
+    pub provenance: BTreeSet<String>,
+}
+
+/// A merged view over several cross reference modules, where each link and chain only appears once
+#[derive(Debug, Default)]
+pub struct MergedXRefs
+{
+    pub links: Vec<MergedLink>,
+    pub chains: Vec<MergedChain>,
+}
+
+#[derive(Default)]
+struct PairInfo
+{
+    forward: BTreeSet<String>,
+    backward: BTreeSet<String>,
+    votes: Option<i32>,
+    /// The texts of the first and second reference of the key
+    texts: (Option<String>, Option<String>),
+    notes: BTreeSet<String>,
+}
+
+impl MergedXRefs
+{
+    pub fn merge(modules: &[&XRefModule]) -> Self
+    {
+        // keyed by the pair in the order it was first seen, so the output is deterministic
+        let mut order: Vec<(RefId, RefId)> = vec![];
+        let mut pairs = HashMap::<(RefId, RefId), PairInfo>::new();
+
+        let mut add = |from: (&RefId, Option<&String>), to: (&RefId, Option<&String>), votes: Option<i32>, note: Option<&String>, module: &str| {
+            if from.0 == to.0
+            {
+                return;
+            }
+
+            let (key, forward) = if pairs.contains_key(&(to.0.clone(), from.0.clone())) {
+                ((to.0.clone(), from.0.clone()), false)
+            } else {
+                ((from.0.clone(), to.0.clone()), true)
+            };
+
+            let info = pairs.entry(key.clone()).or_insert_with(|| {
+                order.push(key);
+                PairInfo::default()
+            });
+
+            let (first, second) = if forward { (from.1, to.1) } else { (to.1, from.1) };
+            if forward
+            {
+                info.forward.insert(module.to_owned());
+            }
+            else
+            {
+                info.backward.insert(module.to_owned());
+            }
+
+            info.votes = info.votes.max(votes);
+            info.texts.0 = info.texts.0.take().or_else(|| first.cloned());
+            info.texts.1 = info.texts.1.take().or_else(|| second.cloned());
+            info.notes.extend(note.cloned());
+        };
+
+        let mut chains = Vec::<MergedChain>::new();
+        let mut chain_indices = HashMap::<(&str, &[RefId]), usize>::new();
+
+        for module in modules
+        {
+            for xref in module.refs.iter()
+            {
+                match xref
+                {
+                    XRef::Directed { source, source_text, targets, note } => {
+                        targets.iter().for_each(|t| add((source, source_text.as_ref()), (&t.id, None), t.votes, note.as_ref(), &module.name));
+                    },
+                    XRef::Mutual { refs, note } => {
+                        for (a, b) in refs.iter().tuple_combinations()
+                        {
+                            add((&a.id, a.text.as_ref()), (&b.id, b.text.as_ref()), None, note.as_ref(), &module.name);
+                            add((&b.id, b.text.as_ref()), (&a.id, a.text.as_ref()), None, note.as_ref(), &module.name);
+                        }
+                    },
+                    XRef::Chain { topic, refs, note } => {
+                        let i = *chain_indices.entry((topic.as_str(), refs.as_slice())).or_insert_with(|| {
+                            chains.push(MergedChain { topic: topic.clone(), refs: refs.clone(), notes: BTreeSet::new(), provenance: BTreeSet::new() });
+                            chains.len() - 1
+                        });
+
+                        chains[i].notes.extend(note.clone());
+                        chains[i].provenance.insert(module.name.clone());
+                    },
+                }
+            }
+        }
+
+        let links = order.into_iter().map(|key| {
+            let info = pairs.remove(&key).unwrap();
+            let mutual = !info.forward.is_empty() && !info.backward.is_empty();
+            let ((from, from_text), (to, to_text)) = if info.forward.is_empty() {
+                ((key.1, info.texts.1), (key.0, info.texts.0))
+            } else {
+                ((key.0, info.texts.0), (key.1, info.texts.1))
+            };
+
+            MergedLink {
+                from,
+                to,
+                mutual,
+                votes: info.votes,
+                from_text,
+                to_text,
+                notes: info.notes,
+                provenance: info.forward.into_iter().chain(info.backward).collect(),
+            }
+        }).collect_vec();
+
+        Self { links: remove_overlapping(links), chains }
+    }
+
+    /// The links from a reference, including mutual links where it is the `to` side
+    pub fn links_from(&self, id: &RefId) -> Vec<&MergedLink>
+    {
+        self.links.iter().filter(|l| l.from == *id || (l.mutual && l.to == *id)).collect()
+    }
+
+    pub fn to_module(&self, config: XRefsConfig) -> XRefModule
+    {
+        let mut refs = vec![];
+        let mut directed = HashMap::<(&RefId, Option<String>), usize>::new();
+
+        for link in self.links.iter()
+        {
+            let note = join_notes(&link.notes);
+            if link.mutual
+            {
+                refs.push(XRef::Mutual {
+                    refs: vec![
+                        MutualRef { id: link.from.clone(), text: link.from_text.clone() },
+                        MutualRef { id: link.to.clone(), text: link.to_text.clone() }
+                    ],
+                    note
+                });
+                continue;
+            }
+
+            // links are only grouped under one source when they have the same notes, as the note belongs to the whole reference
+            let target = XRefTarget { id: link.to.clone(), votes: link.votes };
+            match directed.get(&(&link.from, note.clone()))
+            {
+                Some(i) => if let XRef::Directed { source: _, source_text, targets, note: _ } = &mut refs[*i]
+                {
+                    targets.push(target);
+                    if source_text.is_none()
+                    {
+                        source_text.clone_from(&link.from_text);
+                    }
+                },
+                None => {
+                    directed.insert((&link.from, note.clone()), refs.len());
+                    refs.push(XRef::Directed { source: link.from.clone(), source_text: link.from_text.clone(), targets: vec![target], note });
+                }
+            }
+        }
+
+        refs.extend(self.chains.iter().map(|c| XRef::Chain { topic: c.topic.clone(), refs: c.refs.clone(), note: join_notes(&c.notes) }));
+        XRefModule::new(config, refs)
+    }
+
+    /// Writes the merged references as a new module, `{dir}/{name}.toml` and `{dir}/{name}.jsonl`
+    pub fn save(&self, dir_path: &str, name: &str, config: XRefsConfig) -> Result<(), LoadError>
+    {
+        self.to_module(config).save(dir_path, name)
+    }
+}
+
+fn join_notes(notes: &BTreeSet<String>) -> Option<String>
+{
+    (!notes.is_empty()).then(|| notes.iter().join("; "))
+}
+
+// drops links covered by another link with the same kind, where both ends of the covered link are inside the ends of the other,
+// e.g. `Gen.1.1 -> John.1.1` when `Gen.1.1 -> John.1.1-John.1.3` exists. Candidates share one end exactly, and mutual links are compared either way round
+fn remove_overlapping(links: Vec<MergedLink>) -> Vec<MergedLink>
+{
+    #[derive(PartialEq, Eq, Hash)]
+    enum End { From, To, Either }
+
+    let mut groups = HashMap::<(&RefId, End), Vec<(usize, &RefId)>>::new();
+    for (i, link) in links.iter().enumerate()
+    {
+        let (from, to) = if link.mutual { (End::Either, End::Either) } else { (End::From, End::To) };
+        groups.entry((&link.from, from)).or_default().push((i, &link.to));
+        groups.entry((&link.to, to)).or_default().push((i, &link.from));
+    }
+
+    // in order of the first link of each group, so which link is kept does not depend on the hash map order
+    let mut absorbed_into = HashMap::<usize, usize>::new();
+    for group in groups.into_values().filter(|g| g.len() > 1).sorted_by_key(|g| g[0].0)
+    {
+        for (&(i, a), &(j, b)) in group.iter().tuple_combinations()
+        {
+            if i == j || absorbed_into.contains_key(&i) || absorbed_into.contains_key(&j)
+            {
+                continue;
+            }
+
+            if ref_covers(a, b)
+            {
+                absorbed_into.insert(j, i);
+            }
+            else if ref_covers(b, a)
+            {
+                absorbed_into.insert(i, j);
+            }
+        }
+    }
+
+    let mut links = links;
+    for &from in absorbed_into.keys().sorted()
+    {
+        let mut into = absorbed_into[&from];
+        while let Some(next) = absorbed_into.get(&into)
+        {
+            into = *next;
+        }
+
+        let absorbed = links[from].clone();
+        let link = &mut links[into];
+        link.provenance.extend(absorbed.provenance);
+        link.notes.extend(absorbed.notes);
+        link.votes = link.votes.max(absorbed.votes);
+    }
+
+    links.into_iter()
+        .enumerate()
+        .filter(|(i, _)| !absorbed_into.contains_key(i))
+        .map(|(_, l)| l)
+        .collect()
+}
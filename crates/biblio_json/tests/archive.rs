@@ -0,0 +1,86 @@
+use std::{fs::{self, File}, io::Write, path::Path, str::FromStr};
+
+use biblio_json::{archive, error::LoadError, ref_id::RefId, LoadOptions, Package};
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/round_trip");
+
+fn assert_matches_fixture(package: &Package)
+{
+    let fixture = Package::load(FIXTURE).unwrap();
+    assert_eq!(package.name, fixture.name);
+    assert_eq!(package.module_ids().collect::<Vec<_>>(), fixture.module_ids().collect::<Vec<_>>());
+
+    let bible = package.bible("mini").unwrap();
+    assert_eq!(bible.source.verses.len(), fixture.bible("mini").unwrap().source.verses.len());
+    assert!(bible.source.verses.contains_key(&RefId::from_str("John.1.1").unwrap()));
+}
+
+fn write_zip(path: &Path, files: &[(&str, &str)])
+{
+    let mut zip = ZipWriter::new(File::create(path).unwrap());
+    for (name, contents) in files
+    {
+        zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+        zip.write_all(contents.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+#[test]
+fn packed_packages_load_in_every_format()
+{
+    let dir = tempfile::tempdir().unwrap();
+    for name in ["mini.zip", "mini.tar.gz", "mini.tgz"]
+    {
+        let path = dir.path().join(name);
+        archive::pack(FIXTURE, &path).unwrap();
+        assert_matches_fixture(&Package::load(path.to_str().unwrap()).unwrap());
+    }
+}
+
+#[test]
+fn lazy_modules_outlive_the_load()
+{
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("mini.zip");
+    archive::pack(FIXTURE, &path).unwrap();
+
+    // the extracted files are kept until the package is dropped, so modules can still be loaded later
+    let package = Package::load_with(path.to_str().unwrap(), &LoadOptions::lazy()).unwrap();
+    assert!(package.modules.iter().all(|m| !m.is_loaded()));
+    assert_matches_fixture(&package);
+}
+
+#[test]
+fn errors_point_into_the_archive()
+{
+    let dir = tempfile::tempdir().unwrap();
+    let package_dir = dir.path().join("mini");
+    Package::load(FIXTURE).unwrap().save(package_dir.to_str().unwrap()).unwrap();
+    fs::write(package_dir.join("bibles/mini.jsonl"), "{\"id\":").unwrap();
+
+    let path = dir.path().join("mini.zip");
+    archive::pack(&package_dir, &path).unwrap();
+
+    let errors = Package::load(path.to_str().unwrap()).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].path(), format!("{}/bibles/mini.jsonl", path.display()));
+}
+
+#[test]
+fn rejects_archives_that_are_not_packages()
+{
+    let dir = tempfile::tempdir().unwrap();
+
+    let nested = dir.path().join("nested.zip");
+    write_zip(&nested, &[("mini/biblio-json.toml", "name = \"Mini\"")]);
+    let errors = Package::load(nested.to_str().unwrap()).unwrap_err();
+    assert!(matches!(&errors[..], [LoadError::Config { .. }]), "{:?}", errors);
+
+    let unsafe_path = dir.path().join("unsafe.zip");
+    write_zip(&unsafe_path, &[("biblio-json.toml", "name = \"Mini\""), ("../outside.txt", "")]);
+    let errors = Package::load(unsafe_path.to_str().unwrap()).unwrap_err();
+    assert!(errors[0].to_string().contains("unsafe path"), "{}", errors[0]);
+    assert!(!dir.path().parent().unwrap().join("outside.txt").exists());
+}
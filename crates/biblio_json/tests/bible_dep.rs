@@ -0,0 +1,59 @@
+use std::fs;
+
+use biblio_json::{Package, PackageValidationError};
+use tempfile::TempDir;
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/round_trip");
+
+/// A copy of the fixture package without its dictionary, with a second Bible that only has Genesis, and the xref module depending on `bible_dep`
+fn package_with_bible_dep(bible_dep: Option<&str>) -> TempDir
+{
+    let dir = tempfile::tempdir().unwrap();
+    Package::load(FIXTURE).unwrap().save(dir.path().to_str().unwrap()).unwrap();
+    fs::remove_dir_all(dir.path().join("dictionaries")).unwrap();
+
+    fs::write(dir.path().join("bibles/genesis.toml"), "name = \"Genesis\"\ndescription = \"Only Genesis\"\nlanguage = \"en\"\n\n[books]\nGen = \"Genesis\"\n").unwrap();
+    fs::write(dir.path().join("bibles/genesis.jsonl"), concat!(
+        "{\"id\":\"Gen.1.1\",\"words\":[]}\n",
+        "{\"id\":\"Gen.1.2\",\"words\":[]}\n",
+        "{\"id\":\"Gen.2.1\",\"words\":[]}\n",
+    )).unwrap();
+
+    let config = bible_dep.map_or(String::new(), |dep| format!("bible_dep = \"{}\"\n", dep));
+    fs::write(dir.path().join("cross_references/links.toml"), format!("name = \"Links\"\n{}", config)).unwrap();
+    dir
+}
+
+fn validate(dir: &TempDir) -> Vec<PackageValidationError>
+{
+    Package::load(dir.path().to_str().unwrap()).unwrap().validate().err().unwrap_or_default()
+}
+
+#[test]
+fn xrefs_are_only_checked_against_their_bible_dep()
+{
+    // every reference is in `mini`, so the Bible without John is not used
+    assert!(validate(&package_with_bible_dep(Some("mini"))).is_empty());
+
+    let errors = validate(&package_with_bible_dep(Some("genesis")));
+    let invalid = errors.iter().map(|e| match e {
+        PackageValidationError::InvalidRefId { id, bible_name, line, .. } => (id.to_string(), bible_name.as_str(), *line),
+        e => panic!("unexpected error: {}", e),
+    }).collect::<Vec<_>>();
+
+    assert_eq!(invalid, vec![
+        ("John.1.1".to_owned(), "Genesis", 1),
+        ("John.1.1".to_owned(), "Genesis", 2),
+        ("John.1.1".to_owned(), "Genesis", 3),
+    ]);
+}
+
+#[test]
+fn reports_missing_and_unknown_bible_deps()
+{
+    let errors = validate(&package_with_bible_dep(None));
+    assert!(matches!(&errors[..], [PackageValidationError::MissingBibleDep { xref_name }] if xref_name == "Links"));
+
+    let errors = validate(&package_with_bible_dep(Some("esv")));
+    assert!(matches!(&errors[..], [PackageValidationError::UnknownBibleDep { bible_dep, .. }] if bible_dep == "esv"));
+}
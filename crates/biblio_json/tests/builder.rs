@@ -0,0 +1,23 @@
+use biblio_json::builder::{BibleBuilder, DictBuilder, ModuleBuilder, PackageBuilder};
+
+#[test]
+fn modules_with_the_same_id_are_rejected()
+{
+    let bible = BibleBuilder::new("KJV", "en").build().unwrap();
+    let dict = DictBuilder::new("Easton", "en").id("kjv").build().unwrap();
+
+    let errors = PackageBuilder::new("Study", "MIT").bible(bible).dictionary(dict).build().unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].to_string().contains("Module id kjv is used by more than one module"), "{}", errors[0]);
+}
+
+#[test]
+fn ids_derived_from_the_name_are_checked()
+{
+    let errors = BibleBuilder::new("   ", "en").build().unwrap_err();
+    assert!(errors[0].to_string().contains("Module ids cannot be empty"), "{}", errors[0]);
+
+    assert!(DictBuilder::new("?!", "en").build().is_err());
+    assert_eq!(DictBuilder::new("?!", "en").id("notes").build().unwrap().id, "notes");
+    assert_eq!(DictBuilder::new("a/b", "en").build().unwrap().id, "a_b");
+}
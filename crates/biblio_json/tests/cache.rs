@@ -0,0 +1,127 @@
+use std::{fs::{self, File}, path::Path, str::FromStr, time::{Duration, SystemTime}};
+
+use biblio_json::{cache, manifest, ref_id::RefId, LoadOptions, Package};
+use tempfile::TempDir;
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/round_trip");
+
+fn copy_of_fixture() -> TempDir
+{
+    let dir = tempfile::tempdir().unwrap();
+    Package::load(FIXTURE).unwrap().save(dir.path().to_str().unwrap()).unwrap();
+    dir
+}
+
+fn load(dir: &Path) -> Package
+{
+    Package::load_with(dir.to_str().unwrap(), &LoadOptions::default().with_cache()).unwrap()
+}
+
+fn first_word(package: &Package) -> String
+{
+    let verse = &package.bible("mini").unwrap().source.verses[&RefId::from_str("Gen.1.1").unwrap()];
+    verse.words[0].text.clone()
+}
+
+fn set_modified(path: &Path, time: SystemTime)
+{
+    File::options().write(true).open(path).unwrap().set_modified(time).unwrap();
+}
+
+#[test]
+fn caches_are_written_next_to_each_data_file()
+{
+    let dir = copy_of_fixture();
+    load(dir.path());
+
+    for data in ["bibles/mini.jsonl", "cross_references/links.jsonl", "dictionaries/names.jsonl", "lexicons/strongs.jsonl"]
+    {
+        assert!(cache::cache_path(dir.path().join(data)).is_file(), "{} has no cache", data);
+    }
+
+    assert_eq!(first_word(&load(dir.path())), "In");
+}
+
+#[test]
+fn edited_files_are_loaded_again()
+{
+    let dir = copy_of_fixture();
+    load(dir.path());
+
+    let data = dir.path().join("bibles/mini.jsonl");
+    fs::write(&data, fs::read_to_string(&data).unwrap().replacen("\"In\"", "\"Then\"", 1)).unwrap();
+    assert_eq!(first_word(&load(dir.path())), "Then");
+
+    let config = dir.path().join("bibles/mini.toml");
+    fs::write(&config, fs::read_to_string(&config).unwrap().replace("A tiny Bible", "A small Bible")).unwrap();
+    assert_eq!(load(dir.path()).bible("mini").unwrap().description, "A small Bible");
+}
+
+#[test]
+fn touched_files_are_hashed_once()
+{
+    let dir = copy_of_fixture();
+    load(dir.path());
+
+    let data = dir.path().join("bibles/mini.jsonl");
+    let cache = cache::cache_path(&data);
+    let before = fs::read(&cache).unwrap();
+
+    // the contents are unchanged, so the cache is kept and given the new modification time
+    set_modified(&data, SystemTime::now() + Duration::from_secs(60));
+    assert_eq!(first_word(&load(dir.path())), "In");
+
+    let after = fs::read(&cache).unwrap();
+    assert_ne!(before, after);
+    assert_eq!(before.len(), after.len());
+
+    load(dir.path());
+    assert_eq!(fs::read(&cache).unwrap(), after);
+}
+
+#[test]
+fn caches_that_cannot_be_decoded_are_rebuilt()
+{
+    let dir = copy_of_fixture();
+    load(dir.path());
+
+    let cache = cache::cache_path(dir.path().join("bibles/mini.jsonl"));
+    let bytes = fs::read(&cache).unwrap();
+    fs::write(&cache, &bytes[..bytes.len() - 8]).unwrap();
+    assert_eq!(first_word(&load(dir.path())), "In");
+    assert_eq!(fs::read(&cache).unwrap().len(), bytes.len());
+
+    // as is a cache from another format version
+    fs::write(&cache, b"BJSC\x00\x00").unwrap();
+    assert_eq!(first_word(&load(dir.path())), "In");
+    assert_eq!(fs::read(&cache).unwrap().len(), bytes.len());
+}
+
+#[test]
+fn modules_that_fail_to_load_are_not_cached()
+{
+    let dir = copy_of_fixture();
+    let data = dir.path().join("bibles/mini.jsonl");
+    fs::write(&data, "{\"id\":").unwrap();
+
+    assert!(Package::load_with(dir.path().to_str().unwrap(), &LoadOptions::default().with_cache()).is_err());
+    assert!(!cache::cache_path(&data).exists());
+}
+
+#[test]
+fn packages_with_a_manifest_are_not_cached()
+{
+    let dir = copy_of_fixture();
+    load(dir.path());
+
+    // an edit that keeps the size and modification time of the file, so the cache still looks up to date
+    let data = dir.path().join("bibles/mini.jsonl");
+    let modified = fs::metadata(&data).unwrap().modified().unwrap();
+    fs::write(&data, fs::read_to_string(&data).unwrap().replacen("\"In\"", "\"At\"", 1)).unwrap();
+    set_modified(&data, modified);
+    assert_eq!(first_word(&load(dir.path())), "In");
+
+    // the manifest verifies the data file, which is then read rather than the unverified cache
+    manifest::write_manifest(dir.path()).unwrap();
+    assert_eq!(first_word(&load(dir.path())), "At");
+}
@@ -0,0 +1,82 @@
+use std::{fs, str::FromStr};
+
+use biblio_json::{builder::{PackageBuilder, XRefBuilder}, modules::xrefs::{XRef, XRefModule}, ref_id::RefId, PackageValidationError};
+
+fn id(s: &str) -> RefId
+{
+    RefId::from_str(s).unwrap()
+}
+
+fn module() -> XRefModule
+{
+    XRefBuilder::new("Thompson")
+        .chain("Faith", [id("Gen.15.6"), id("Hab.2.4"), id("Rom.1.17")])
+        .chain("Righteousness", [id("Gen.15.6"), id("Rom.4.1-Rom.4.8")])
+        .build()
+        .unwrap()
+}
+
+fn link(module: &XRefModule, verse: &str) -> Vec<(String, Option<String>, Option<String>)>
+{
+    module.chain_links(&id(verse)).iter()
+        .map(|l| (l.topic.to_owned(), l.previous.map(|p| p.to_string()), l.next.map(|n| n.to_string())))
+        .collect()
+}
+
+#[test]
+fn navigates_chains_from_any_verse()
+{
+    let module = module();
+
+    let mut links = link(&module, "Gen.15.6");
+    links.sort();
+    assert_eq!(links, vec![
+        ("Faith".to_owned(), None, Some("Hab.2.4".to_owned())),
+        ("Righteousness".to_owned(), None, Some("Rom.4.1-Rom.4.8".to_owned())),
+    ]);
+
+    assert_eq!(link(&module, "Hab.2.4"), vec![("Faith".to_owned(), Some("Gen.15.6".to_owned()), Some("Rom.1.17".to_owned()))]);
+
+    // a verse inside a range in the chain is part of it
+    assert_eq!(link(&module, "Rom.4.3"), vec![("Righteousness".to_owned(), Some("Gen.15.6".to_owned()), None)]);
+    assert!(link(&module, "Rom.4.9").is_empty());
+
+    let faith = &module.refs[0];
+    assert_eq!(faith.chain_next(&id("Hab.2.4")), Some(&id("Rom.1.17")));
+    assert_eq!(faith.chain_previous(&id("Gen.15.6")), None);
+}
+
+#[test]
+fn chains_are_saved_and_loaded()
+{
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().to_str().unwrap();
+    module().save(path, "thompson").unwrap();
+
+    let jsonl = fs::read_to_string(dir.path().join("thompson.jsonl")).unwrap();
+    assert_eq!(jsonl.lines().next(), Some(r#"{"type":"chain","topic":"Faith","refs":["Gen.15.6","Hab.2.4","Rom.1.17"]}"#));
+
+    let module = XRefModule::load(path, "thompson").unwrap();
+    assert!(matches!(&module.refs[1], XRef::Chain { topic, refs, .. } if topic == "Righteousness" && refs.len() == 2));
+}
+
+#[test]
+fn reports_invalid_chains()
+{
+    let xrefs = XRefBuilder::new("Thompson")
+        .chain("Faith", [id("Gen.15.6")])
+        .chain("Hope", [id("Rom.5.5"), id("Rom.8.24"), id("Rom.5.5")])
+        .build()
+        .unwrap();
+
+    let errors = PackageBuilder::new("Chains", "MIT").xrefs(xrefs).build().unwrap().validate().unwrap_err();
+    let chains = errors.iter().filter_map(|e| match e {
+        PackageValidationError::InvalidChain { topic, line, reason, .. } => Some((topic.as_str(), *line, reason.as_str())),
+        _ => None,
+    }).collect::<Vec<_>>();
+
+    assert_eq!(chains, vec![
+        ("Faith", 1, "a chain must contain at least two references"),
+        ("Hope", 2, "Rom.5.5 appears more than once"),
+    ]);
+}
@@ -0,0 +1,52 @@
+use biblio_json::citations::{book_order, extract_refs};
+
+fn extract(text: &str) -> Vec<String>
+{
+    extract_refs(text).iter().map(|id| id.to_string()).collect()
+}
+
+#[test]
+fn finds_abbreviated_and_numbered_books()
+{
+    assert_eq!(extract("See Gen. 14:18 and Ps 23."), vec!["Gen.14.18", "Ps.23"]);
+    assert_eq!(extract("cf. 1 Kings 3:4, 1Jn 1:9 and II Tim 2:15"), vec!["1Kgs.3.4", "1John.1.9", "2Tim.2.15"]);
+    assert_eq!(extract("Song of Solomon 2:1"), vec!["Song.2.1"]);
+}
+
+#[test]
+fn follows_ranges_and_continuations()
+{
+    assert_eq!(
+        extract("1 Kings 3:4-9, 12; 4:1 and Matt 5:3–7:29"),
+        vec!["1Kgs.3.4-1Kgs.3.9", "1Kgs.3.12", "1Kgs.4.1", "Matt.5.3-Matt.7.29"]
+    );
+
+    // a comma followed by another book starts a new citation rather than a verse
+    assert_eq!(extract("Gen 1:1, 2 Kings 2:11"), vec!["Gen.1.1", "2Kgs.2.11"]);
+}
+
+#[test]
+fn ignores_words_that_are_not_citations()
+{
+    assert!(extract("Genesis is the first book, and Job was patient").is_empty());
+    assert!(extract("Joba 3:1, Gen:1, Rev 0:1").is_empty());
+}
+
+#[test]
+fn ambiguous_short_names_need_a_verse()
+{
+    assert!(extract("I Am 5 years old, Co 3 members, Ti 4 and SS 2").is_empty());
+    assert_eq!(extract("Am 5:24 and Co 3:16"), vec!["Amos.5.24", "Col.3.16"]);
+
+    // short names that are not words still match a chapter on its own
+    assert_eq!(extract("Jn 3 and Ps 23"), vec!["John.3", "Ps.23"]);
+}
+
+#[test]
+fn orders_books_canonically()
+{
+    assert_eq!(book_order("Gen"), Some(0));
+    assert_eq!(book_order("Matt"), Some(39));
+    assert_eq!(book_order("Rev"), Some(65));
+    assert_eq!(book_order("Genesis"), None);
+}
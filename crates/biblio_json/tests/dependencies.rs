@@ -0,0 +1,38 @@
+use std::{fs, path::Path};
+
+use biblio_json::{Package, PACKAGE_FILE_NAME};
+
+fn write_package(dir: &Path, name: &str, dependency: &str, path: &str)
+{
+    fs::create_dir_all(dir).unwrap();
+    let config = format!("name = \"{}\"\nversion = \"1.0.0\"\nauthors = []\nlicense = \"MIT\"\n\n[dependencies]\n{} = {{ version = \"^1.0\", path = \"{}\" }}\n", name, dependency, path);
+    fs::write(dir.join(PACKAGE_FILE_NAME), config).unwrap();
+}
+
+#[test]
+fn circular_dependency_with_keys_that_differ_from_names()
+{
+    let root = tempfile::tempdir().unwrap();
+    write_package(&root.path().join("a"), "Alpha", "b", "../b");
+    write_package(&root.path().join("b"), "Beta", "a", "../a");
+
+    let errors = Package::load(&root.path().join("a").display().to_string()).unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].to_string().contains("Circular dependency on a"), "{}", errors[0]);
+}
+
+#[test]
+fn dependency_key_that_differs_from_name()
+{
+    let root = tempfile::tempdir().unwrap();
+    write_package(&root.path().join("a"), "Alpha", "c", "../c");
+
+    let c = root.path().join("c");
+    fs::create_dir_all(&c).unwrap();
+    fs::write(c.join(PACKAGE_FILE_NAME), "name = \"Gamma\"\nversion = \"1.2.0\"\nauthors = []\nlicense = \"MIT\"\n").unwrap();
+
+    let package = Package::load(&root.path().join("a").display().to_string()).unwrap();
+    assert_eq!(package.dependencies.len(), 1);
+    assert_eq!(package.dependencies[0].package.name, "Gamma");
+}
@@ -0,0 +1,44 @@
+use std::{collections::HashMap, fs};
+
+use biblio_json::{diagnostics::{Diagnostic, DiagnosticRenderer, Severity}, modules::bible::BibleSource, LoadOptions};
+
+#[test]
+fn renders_the_offending_line_with_a_hint()
+{
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("kjv.jsonl").display().to_string();
+    fs::write(&path, "{\"id\":\"Gen.1.1\",\"words\":[]}\n{\"id\":\"Gen.3.1\",\"words\":[]}\n").unwrap();
+
+    let books = HashMap::from([("Gen".to_owned(), "Genesis".to_owned())]);
+    let errors = BibleSource::from_file_with(&path, &books, &LoadOptions::default()).unwrap_err();
+    let diagnostic = Diagnostic::from(&errors[0]);
+    assert_eq!(diagnostic.severity, Severity::Error);
+
+    let rendered = DiagnosticRenderer::plain().render(&diagnostic).replace(&path, "kjv.jsonl");
+    assert_eq!(rendered, concat!(
+        "error: verse Gen.3.1 has a chapter number that is out of order\n",
+        " --> kjv.jsonl:2\n",
+        "  |\n",
+        "2 | {\"id\":\"Gen.3.1\",\"words\":[]}\n",
+        "  |        ^^^^^^^\n",
+        "  = hint: chapter 3 follows chapter 1; expected 2\n",
+    ));
+}
+
+#[test]
+fn counts_errors_and_warnings()
+{
+    let diagnostics = [
+        Diagnostic::new(Severity::Warning, "missing year"),
+        Diagnostic::new(Severity::Error, "unknown book"),
+        Diagnostic::new(Severity::Warning, "trailing space"),
+    ];
+
+    let rendered = DiagnosticRenderer::plain().render_all(&diagnostics);
+    assert!(rendered.starts_with("warning: missing year\n\nerror: unknown book\n"), "{}", rendered);
+    assert!(rendered.ends_with("error: 1 error, 2 warnings\n"), "{}", rendered);
+
+    // colors are only used when asked for
+    assert!(!rendered.contains('\x1b'));
+    assert!(DiagnosticRenderer::colored().render(&diagnostics[0]).starts_with("\x1b[1;33mwarning\x1b[0m: "));
+}
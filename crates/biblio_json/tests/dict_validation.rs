@@ -0,0 +1,43 @@
+use std::fs;
+
+use biblio_json::{diagnostics::Severity, Package, PackageValidationError};
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/round_trip");
+
+const NAMES: &str = r#"{"term":"english_label","definitions":["Meaning"]}
+{"term":"Abel","aliases":["Adam"],"definitions":["vanity"]}
+{"term":"Adam","definitions":[]}
+{"term":"abel ","definitions":["breath"]}
+"#;
+
+#[test]
+fn reports_dictionary_problems_with_their_lines()
+{
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().to_str().unwrap();
+    Package::load(FIXTURE).unwrap().save(path).unwrap();
+    fs::write(dir.path().join("dictionaries/names.jsonl"), NAMES).unwrap();
+
+    let errors = Package::load(path).unwrap().validate().unwrap_err();
+    let mut found = errors.iter().map(|e| match e {
+        PackageValidationError::DictHeaderRow { term, line, .. } => format!("header {} {}", line, term),
+        PackageValidationError::DictAliasCollision { alias, other_term, line, .. } => format!("alias {} {} {}", line, alias, other_term),
+        PackageValidationError::EmptyDefinition { term, line, .. } => format!("empty {} {}", line, term),
+        PackageValidationError::DuplicateDictTerm { term, line, first_line, .. } => format!("duplicate {} {} {}", line, term, first_line),
+        PackageValidationError::DictWhitespace { text, line, .. } => format!("whitespace {} {}", line, text),
+        e => panic!("unexpected error: {}", e),
+    }).collect::<Vec<_>>();
+
+    found.sort();
+    assert_eq!(found, vec![
+        "alias 2 Adam Adam",
+        "duplicate 4 abel  2",
+        "empty 3 Adam",
+        "header 1 english_label",
+        "whitespace 4 abel ",
+    ]);
+
+    // formatting problems are warnings, repeated terms and aliases are errors
+    let is_warning = |e: &&PackageValidationError| e.severity() == Severity::Warning;
+    assert_eq!(errors.iter().filter(is_warning).count(), 3);
+}
@@ -0,0 +1,51 @@
+use std::{fs, io::ErrorKind};
+
+use biblio_json::{error::LoadError, modules::{bible::BibleModule, dict::DictModule}, Package};
+
+const CONFIG: &str = "name = \"Mini\"\ndescription = \"A tiny Bible\"\nlanguage = \"en\"\n\n[books]\nGen = \"Genesis\"\n";
+
+#[test]
+fn missing_files_are_io_errors()
+{
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().to_str().unwrap();
+
+    let error = Package::load(path).unwrap_err().remove(0);
+    assert!(matches!(&error, LoadError::Io { kind: ErrorKind::NotFound, .. }), "{:?}", error);
+    assert!(error.path().ends_with("biblio-json.toml"));
+
+    let error = DictModule::load(path, "names").unwrap_err();
+    assert!(matches!(error, LoadError::Io { kind: ErrorKind::NotFound, .. }));
+}
+
+#[test]
+fn toml_errors_have_a_line_and_column()
+{
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("mini.toml"), "name = \"Mini\"\ndescription = \"A tiny Bible\"\nlanguage = en\n").unwrap();
+
+    match BibleModule::load(dir.path().to_str().unwrap(), "mini").unwrap_err()
+    {
+        LoadError::Toml { path, line, column, message: _ } => {
+            assert!(path.ends_with("mini.toml"));
+            assert_eq!((line, column), (Some(3), Some(12)));
+        },
+        e => panic!("expected a toml error, found {:?}", e),
+    }
+}
+
+#[test]
+fn json_line_errors_keep_their_line()
+{
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("mini.toml"), CONFIG).unwrap();
+    fs::write(dir.path().join("mini.jsonl"), "{\"id\":\"Gen.1.1\",\"words\":[]}\n{\"id\":\"Gen.1.2\",\"words\":}\n").unwrap();
+
+    let error = BibleModule::load(dir.path().to_str().unwrap(), "mini").unwrap_err();
+    assert!(matches!(&error, LoadError::JsonLine { line: 2, column: 25, .. }), "{:?}", error);
+    assert_eq!(error.line(), Some(2));
+
+    // load errors can be used as any other error
+    let error: Box<dyn std::error::Error> = Box::new(error);
+    assert!(error.to_string().contains("mini.jsonl"), "{}", error);
+}
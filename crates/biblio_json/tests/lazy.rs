@@ -0,0 +1,55 @@
+use std::{fs, thread};
+
+use biblio_json::{LoadOptions, Package};
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/round_trip");
+
+#[test]
+fn only_configs_are_read_until_a_module_is_used()
+{
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().to_str().unwrap();
+    Package::load(FIXTURE).unwrap().save(path).unwrap();
+    fs::write(dir.path().join("dictionaries/names.jsonl"), "{\"term\":").unwrap();
+
+    // the broken dictionary is not read, so the package loads and its metadata is there
+    let package = Package::load_with(path, &LoadOptions::lazy()).unwrap();
+    assert_eq!(package.package_module("names").unwrap().name(), "Names");
+    assert!(package.modules.iter().all(|m| !m.is_loaded()));
+
+    assert!(package.bible("mini").is_some());
+    let loaded = package.modules.iter().filter(|m| m.is_loaded()).map(|m| m.id.as_str()).collect::<Vec<_>>();
+    assert_eq!(loaded, vec!["mini"]);
+
+    let errors = package.package_module("names").unwrap().get().unwrap_err();
+    assert!(errors[0].path().ends_with("names.jsonl"));
+    assert!(package.dictionary("names").is_none());
+}
+
+#[test]
+fn modules_are_loaded_once_and_read_again_after_unloading()
+{
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().to_str().unwrap();
+    Package::load(FIXTURE).unwrap().save(path).unwrap();
+    let mut package = Package::load_with(path, &LoadOptions::lazy()).unwrap();
+
+    // threads asking at the same time share one loaded value
+    let module = package.package_module("strongs").unwrap();
+    let addresses = thread::scope(|s| {
+        let handles = (0..4).map(|_| s.spawn(|| module.get().unwrap() as *const _ as usize)).collect::<Vec<_>>();
+        handles.into_iter().map(|h| h.join().unwrap()).collect::<Vec<_>>()
+    });
+    assert!(addresses.iter().all(|a| *a == addresses[0]));
+
+    fs::write(dir.path().join("lexicons/strongs.jsonl"), "{\"strongs\":\"H1\",\"lemma\":\"אָב\",\"definitions\":[]}\n").unwrap();
+    assert!(package.lexicon("strongs").unwrap().find_strongs_str("H430").is_some());
+
+    let module = package.modules.iter_mut().find(|m| m.id == "strongs").unwrap();
+    module.unload();
+    assert!(!module.is_loaded());
+
+    let lexicon = package.lexicon("strongs").unwrap();
+    assert!(lexicon.find_strongs_str("H430").is_none());
+    assert!(lexicon.find_strongs_str("H1").is_some());
+}
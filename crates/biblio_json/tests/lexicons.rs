@@ -0,0 +1,46 @@
+use std::{fs, str::FromStr};
+
+use biblio_json::{modules::lexicon::{LexiconModule, StrongsLanguage, StrongsNumber}, LoadOptions, Package};
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/round_trip");
+
+#[test]
+fn finds_entries_by_strongs_number_and_lemma()
+{
+    let package = Package::load(FIXTURE).unwrap();
+    let lexicon = package.lexicon("strongs").unwrap();
+
+    let entry = lexicon.find_strongs_str("H0430").unwrap();
+    assert_eq!(entry.gloss.as_deref(), Some("God"));
+    assert!(lexicon.find_strongs_str("G430").is_none());
+
+    // vowel points are ignored when looking up a lemma
+    assert_eq!(lexicon.find_lemma("אלהים").len(), 1);
+    assert!(lexicon.find_lemma("אלה").is_empty());
+}
+
+#[test]
+fn parses_strongs_numbers()
+{
+    assert_eq!(StrongsNumber::from_str("h1254A"), Ok(StrongsNumber { language: StrongsLanguage::Hebrew, number: 1254, suffix: Some('a') }));
+    assert_eq!(StrongsNumber::from_str("G25").unwrap().to_string(), "G25");
+    assert!(StrongsNumber::from_str("X25").is_err());
+    assert!(StrongsNumber::from_str("H25ab").is_err());
+}
+
+#[test]
+fn reports_duplicate_strongs_numbers()
+{
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("strongs.toml"), "name = \"Strongs\"\nauthors = []\nlanguage = \"he\"\n").unwrap();
+    fs::write(dir.path().join("strongs.jsonl"), concat!(
+        "{\"strongs\":\"H430\",\"lemma\":\"אֱלֹהִים\",\"definitions\":[]}\n",
+        "{\"strongs\":\"H1\",\"lemma\":\"אָב\",\"definitions\":[]}\n",
+        "{\"strongs\":\"H0430\",\"lemma\":\"אֱלֹהִים\",\"definitions\":[]}\n",
+    )).unwrap();
+
+    let errors = LexiconModule::load_with(dir.path().to_str().unwrap(), "strongs", &LoadOptions::accumulate()).unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].line(), Some(3));
+    assert!(errors[0].to_string().contains("already defined on line 1"), "{}", errors[0]);
+}
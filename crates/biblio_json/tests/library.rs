@@ -0,0 +1,95 @@
+use std::{fs, path::Path, sync::Arc};
+
+use biblio_json::{library::Library, LoadOptions, PACKAGE_FILE_NAME};
+
+fn write_package(dir: &Path, name: &str, version: &str, dependencies: &str)
+{
+    fs::create_dir_all(dir).unwrap();
+    let config = format!("name = \"{}\"\nversion = \"{}\"\nauthors = []\nlicense = \"MIT\"\n\n[dependencies]\n{}", name, version, dependencies);
+    fs::write(dir.join(PACKAGE_FILE_NAME), config).unwrap();
+}
+
+#[test]
+fn dependencies_are_shared_with_the_library()
+{
+    let root = tempfile::tempdir().unwrap();
+    write_package(&root.path().join("kjv"), "kjv", "1.0.0", "");
+    write_package(&root.path().join("tsk"), "tsk", "1.0.0", "kjv = \"^1.0\"\n");
+    write_package(&root.path().join("notes"), "notes", "1.0.0", "kjv = \"^1.0\"\n");
+
+    let library = Library::load(root.path()).unwrap();
+    assert!(library.errors.is_empty());
+
+    let kjv = &library.packages.iter().find(|p| p.id == "kjv").unwrap().package;
+    for id in ["tsk", "notes"]
+    {
+        let package = &library.packages.iter().find(|p| p.id == id).unwrap().package;
+        assert!(Arc::ptr_eq(kjv, &package.dependencies[0].package));
+    }
+}
+
+#[test]
+fn lower_versions_are_shadowed_before_loading()
+{
+    let root = tempfile::tempdir().unwrap();
+    // the shadowed package would fail to load, so it must not have been loaded
+    write_package(&root.path().join("kjv_1"), "kjv", "1.0.0", "missing = \"^1.0\"\n");
+    write_package(&root.path().join("kjv_2"), "kjv", "2.0.0", "");
+
+    let library = Library::load(root.path()).unwrap();
+    assert!(library.errors.is_empty(), "{:?}", library.errors);
+    assert_eq!(library.packages.len(), 1);
+    assert_eq!(library.packages[0].package.version.as_ref().unwrap().major, 2);
+    assert_eq!(library.shadowed, vec![root.path().join("kjv_1")]);
+}
+
+#[test]
+fn errors_are_kept_per_package()
+{
+    let root = tempfile::tempdir().unwrap();
+    write_package(&root.path().join("kjv"), "kjv", "1.0.0", "");
+    write_package(&root.path().join("tsk"), "tsk", "1.0.0", "missing = \"^1.0\"\n");
+
+    let library = Library::load(root.path()).unwrap();
+    assert_eq!(library.packages.len(), 1);
+    assert_eq!(library.packages[0].id, "kjv");
+    assert_eq!(library.errors.len(), 1);
+    assert_eq!(library.errors[0].0, root.path().join("tsk"));
+}
+
+fn copy_module(dir: &Path, from: &str, to: &str)
+{
+    let fixture = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/round_trip"));
+    let (folder, _) = from.split_once('/').unwrap();
+    fs::create_dir_all(dir.join(folder)).unwrap();
+    for extension in ["toml", "jsonl"]
+    {
+        fs::copy(fixture.join(format!("{}.{}", from, extension)), dir.join(format!("{}/{}.{}", folder, to, extension))).unwrap();
+    }
+}
+
+#[test]
+fn unqualified_lookups_match_on_kind_and_only_load_the_match()
+{
+    let root = tempfile::tempdir().unwrap();
+    let bibles = root.path().join("bibles");
+    let lexicons = root.path().join("lexicons");
+    copy_module(&bibles, "bibles/mini", "mini");
+    copy_module(&lexicons, "lexicons/strongs", "mini");
+    let config = |paths: &str| format!("name = \"{0}\"\nversion = \"1.0.0\"\nauthors = []\nlicense = \"MIT\"\n\n[module_paths]\n{0} = \"{0}/*.toml\"\n", paths);
+    fs::write(bibles.join(PACKAGE_FILE_NAME), config("bibles")).unwrap();
+    fs::write(lexicons.join(PACKAGE_FILE_NAME), config("lexicons")).unwrap();
+
+    let library = Library::load_with(root.path(), &LoadOptions::lazy()).unwrap();
+    assert!(library.errors.is_empty(), "{:?}", library.errors);
+    let is_loaded = |id: &str| library.modules().find(|(qualified, _)| qualified == id).unwrap().1.is_loaded();
+
+    assert!(library.bible("mini").is_some());
+    assert!(is_loaded("bibles/mini"));
+    assert!(!is_loaded("lexicons/mini"));
+
+    assert!(library.lexicon("mini").is_some());
+    assert!(library.dictionary("mini").is_none());
+    assert!(library.module("mini").is_none());
+    assert!(library.module("lexicons/mini").is_some());
+}
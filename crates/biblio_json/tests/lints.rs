@@ -0,0 +1,59 @@
+use std::collections::BTreeMap;
+
+use biblio_json::{builder::PackageBuilder, lints::{self, is_spdx_expression, LintLevel}};
+
+#[test]
+fn accepts_spdx_expressions()
+{
+    for license in [
+        "MIT",
+        "cc0-1.0",
+        "MPL-1.1+",
+        "LicenseRef-Public-Domain",
+        "Apache-2.0 WITH LLVM-exception",
+        "CC-BY-SA-4.0 OR CC0-1.0",
+        "(MIT OR CC0-1.0) AND CC-BY-4.0",
+        "((MIT))",
+        // from the full SPDX list, rather than only the most common licenses
+        "BSD-2-Clause-Patent",
+        "CC-BY-NC-SA-3.0-DE",
+        "GPL-2.0-only WITH Classpath-exception-2.0 OR Artistic-1.0-Perl",
+    ]
+    {
+        assert!(is_spdx_expression(license), "{}", license);
+    }
+}
+
+#[test]
+fn rejects_other_licenses()
+{
+    for license in [
+        "",
+        "Public Domain",
+        "MIT AND",
+        "OR MIT",
+        "MIT CC0-1.0",
+        "(MIT OR CC0-1.0",
+        "MIT OR CC0-1.0)",
+        "MIT WITH",
+        "MIT WITH Not-An-Exception",
+        "mit or cc0-1.0",
+    ]
+    {
+        assert!(!is_spdx_expression(license), "{}", license);
+    }
+}
+
+#[test]
+fn reports_non_spdx_package_licenses()
+{
+    let package = PackageBuilder::new("Notes", "Public Domain").author("Nate Craver").build().unwrap();
+
+    let report = lints::lint_package(&package, &BTreeMap::new());
+    assert_eq!(report.findings.len(), 1);
+    assert_eq!(report.findings[0].lint, lints::NON_SPDX_LICENSE.name);
+    assert_eq!(report.findings[0].level, LintLevel::Warn);
+
+    let levels = BTreeMap::from([(lints::NON_SPDX_LICENSE.name.to_owned(), LintLevel::Allow)]);
+    assert!(lints::lint_package(&package, &levels).findings.is_empty());
+}
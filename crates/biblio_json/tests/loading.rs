@@ -0,0 +1,56 @@
+use std::{fs, num::NonZeroUsize, path::Path};
+
+use biblio_json::{LoadOptions, Package, PACKAGE_FILE_NAME};
+
+/// A package of Bibles named `names`, each with two lines that fail to parse
+fn broken_package(dir: &Path, names: &[&str])
+{
+    fs::write(dir.join(PACKAGE_FILE_NAME), "name = \"Broken\"\nauthors = []\nlicense = \"MIT\"\n\n[module_paths]\nbibles = \"bibles/*.toml\"\n").unwrap();
+    fs::create_dir(dir.join("bibles")).unwrap();
+
+    for name in names
+    {
+        let config = format!("name = \"{}\"\ndescription = \"\"\nlanguage = \"en\"\n\n[books]\nGen = \"Genesis\"\n", name);
+        fs::write(dir.join(format!("bibles/{}.toml", name)), config).unwrap();
+        fs::write(dir.join(format!("bibles/{}.jsonl", name)), "{\"id\":\n{\"id\":\n").unwrap();
+    }
+}
+
+/// The file name and line of each error
+fn locations(dir: &Path, options: &LoadOptions) -> Vec<(String, Option<usize>)>
+{
+    Package::load_with(dir.to_str().unwrap(), options).unwrap_err().iter().map(|e| {
+        let name = Path::new(e.path()).file_name().unwrap().to_string_lossy().into_owned();
+        (name, e.line())
+    }).collect()
+}
+
+#[test]
+fn errors_are_cut_off_in_module_order()
+{
+    let dir = tempfile::tempdir().unwrap();
+    broken_package(dir.path(), &["a", "b", "c", "d", "e", "f", "g", "h"]);
+
+    let options = LoadOptions::accumulate().with_error_limit(NonZeroUsize::new(3).unwrap());
+    let expected = vec![("a.jsonl".to_owned(), Some(1)), ("a.jsonl".to_owned(), Some(2)), ("b.jsonl".to_owned(), Some(1))];
+
+    // with two threads the last modules load alongside the first, and may reach the limit before the second module starts
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+    for _ in 0..20
+    {
+        assert_eq!(locations(dir.path(), &options), expected);
+        assert_eq!(pool.install(|| locations(dir.path(), &options)), expected);
+    }
+}
+
+#[test]
+fn only_the_first_failing_module_is_reported()
+{
+    let dir = tempfile::tempdir().unwrap();
+    broken_package(dir.path(), &["a", "b", "c", "d"]);
+
+    for _ in 0..20
+    {
+        assert_eq!(locations(dir.path(), &LoadOptions::default()), vec![("a.jsonl".to_owned(), Some(1))]);
+    }
+}
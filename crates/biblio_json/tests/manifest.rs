@@ -0,0 +1,100 @@
+use std::{fs, path::Path};
+
+use biblio_json::{error::{IntegrityError, LoadError}, lints, manifest::{self, Manifest}, Package};
+use tempfile::TempDir;
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/round_trip");
+
+/// A copy of the fixture package with a manifest
+fn package_with_manifest() -> TempDir
+{
+    let dir = tempfile::tempdir().unwrap();
+    Package::load(FIXTURE).unwrap().save(dir.path().to_str().unwrap()).unwrap();
+    manifest::write_manifest(dir.path()).unwrap();
+    dir
+}
+
+fn integrity_errors(dir: &Path) -> Vec<(String, IntegrityError)>
+{
+    let mut errors = Package::load(dir.to_str().unwrap()).unwrap_err().into_iter().map(|e| match e {
+        LoadError::Integrity { path, error } => (path, error),
+        e => panic!("expected an integrity error, found {:?}", e),
+    }).collect::<Vec<_>>();
+
+    errors.sort_by(|a, b| a.0.cmp(&b.0));
+    errors
+}
+
+#[test]
+fn lists_every_package_file()
+{
+    let dir = package_with_manifest();
+    let manifest = Manifest::load(dir.path()).unwrap().unwrap();
+
+    let paths = manifest.files.iter().map(|f| f.path.as_str()).collect::<Vec<_>>();
+    assert_eq!(paths, vec![
+        "bibles/mini.jsonl",
+        "bibles/mini.toml",
+        "biblio-json.toml",
+        "cross_references/links.jsonl",
+        "cross_references/links.toml",
+        "dictionaries/names.jsonl",
+        "dictionaries/names.toml",
+        "lexicons/strongs.jsonl",
+        "lexicons/strongs.toml",
+    ]);
+
+    let package = Package::load(dir.path().to_str().unwrap()).unwrap();
+    assert!(package.unlisted_files.is_empty());
+}
+
+#[test]
+fn reports_every_changed_file()
+{
+    let dir = package_with_manifest();
+    let path = |file: &str| dir.path().join(file);
+
+    let mut bible = fs::read(path("bibles/mini.jsonl")).unwrap();
+    bible.push(b'\n');
+    fs::write(path("bibles/mini.jsonl"), bible).unwrap();
+
+    let mut names = fs::read(path("dictionaries/names.jsonl")).unwrap();
+    names[0] = b' ';
+    fs::write(path("dictionaries/names.jsonl"), names).unwrap();
+
+    fs::remove_file(path("lexicons/strongs.jsonl")).unwrap();
+
+    let errors = integrity_errors(dir.path());
+    assert_eq!(errors.len(), 3);
+    assert_eq!(errors[0].0, path("bibles/mini.jsonl").display().to_string());
+    assert!(matches!(errors[0].1, IntegrityError::Size { expected, found } if found == expected + 1));
+    assert!(matches!(errors[1].1, IntegrityError::Checksum { .. }));
+    assert_eq!(errors[2].1, IntegrityError::Missing);
+}
+
+#[test]
+fn unlisted_files_are_linted_rather_than_rejected()
+{
+    let dir = package_with_manifest();
+    fs::write(dir.path().join("notes.txt"), "not in the manifest").unwrap();
+    fs::write(dir.path().join(".DS_Store"), "hidden").unwrap();
+    fs::write(dir.path().join("bibles/mini.jsonl.cache"), "a cache").unwrap();
+
+    let package = Package::load(dir.path().to_str().unwrap()).unwrap();
+    assert_eq!(package.unlisted_files, vec!["notes.txt"]);
+
+    let report = package.lint();
+    assert!(report.findings.iter().any(|f| f.lint == lints::UNLISTED_FILE.name && f.message.contains("notes.txt")));
+}
+
+#[test]
+fn hidden_module_files_are_not_loaded()
+{
+    let dir = package_with_manifest();
+    fs::copy(dir.path().join("bibles/mini.toml"), dir.path().join("bibles/.evil.toml")).unwrap();
+    fs::copy(dir.path().join("bibles/mini.jsonl"), dir.path().join("bibles/.evil.jsonl")).unwrap();
+
+    let package = Package::load(dir.path().to_str().unwrap()).unwrap();
+    assert_eq!(package.module_ids().filter(|id| id.contains("evil")).count(), 0);
+    assert_eq!(package.modules.len(), 4);
+}
@@ -0,0 +1,70 @@
+use std::str::FromStr;
+
+use biblio_json::{builder::{DictBuilder, PackageBuilder}, markup::{Markup, Span}, ref_id::RefId, PackageValidationError};
+
+#[test]
+fn parses_nested_and_linked_spans()
+{
+    let markup = Markup::parse("King of {see:Salem|Salem}, {i:see {ref:Gen.14.18}} ({heb:מַלְכִּי־צֶדֶק})").unwrap();
+
+    assert_eq!(markup.spans, vec![
+        Span::Text("King of ".into()),
+        Span::EntryLink { term: "Salem".into(), text: Some("Salem".into()) },
+        Span::Text(", ".into()),
+        Span::Italic(vec![Span::Text("see ".into()), Span::RefLink { id: RefId::from_str("Gen.14.18").unwrap(), text: None }]),
+        Span::Text(" (".into()),
+        Span::Hebrew("מַלְכִּי־צֶדֶק".into()),
+        Span::Text(")".into()),
+    ]);
+
+    assert_eq!(markup.entry_links(), vec!["Salem"]);
+    assert_eq!(markup.ref_links(), vec![&RefId::from_str("Gen.14.18").unwrap()]);
+}
+
+#[test]
+fn escaped_characters_are_text()
+{
+    let markup = Markup::parse(r"a \{b\} \| \\").unwrap();
+    assert_eq!(markup.spans, vec![Span::Text(r"a {b} | \".into())]);
+}
+
+#[test]
+fn renders_plain_text_and_html()
+{
+    let markup = Markup::parse("{i:<b>} {see:Abram|father} {ref:Gen.1.1}").unwrap();
+
+    assert_eq!(markup.to_plain_text(), "<b> father Gen.1.1");
+    assert_eq!(
+        markup.to_html(),
+        "<i>&lt;b&gt;</i> <a class=\"entry-link\" data-term=\"Abram\">father</a> <a class=\"ref-link\" data-ref=\"Gen.1.1\">Gen.1.1</a>"
+    );
+}
+
+#[test]
+fn reports_the_offset_of_errors()
+{
+    let error = Markup::parse("see {bold:x}").unwrap_err();
+    assert_eq!(error.message, "Unknown tag `bold`");
+    assert_eq!(error.offset, 4);
+
+    assert!(Markup::parse("{i:unclosed").is_err());
+    assert!(Markup::parse("stray }").is_err());
+    assert!(Markup::parse("{ref:Gen.one}").is_err());
+    assert!(Markup::parse(r"bad \escape").is_err());
+}
+
+#[test]
+fn only_dictionaries_that_opt_in_are_markup()
+{
+    let dictionary = |markup: bool| {
+        let builder = DictBuilder::new("Easton", "en").definition("Abel", ["Hebrew {Hebel}, a breath"]);
+        let builder = if markup { builder.markup() } else { builder };
+        PackageBuilder::new("Easton", "MIT").dictionary(builder.build().unwrap()).build().unwrap()
+    };
+
+    // braces in plain text definitions, as found in older imports, are just text
+    assert!(dictionary(false).validate().is_ok());
+
+    let errors = dictionary(true).validate().unwrap_err();
+    assert!(matches!(&errors[..], [PackageValidationError::InvalidMarkup { .. }]), "{}", errors[0]);
+}
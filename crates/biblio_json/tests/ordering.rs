@@ -0,0 +1,83 @@
+use std::{collections::HashMap, fs, num::NonZeroUsize, str::FromStr};
+
+use biblio_json::{
+    builder::BibleBuilder,
+    error::{LoadError, OrderingError},
+    modules::bible::{BibleSource, Verse},
+    ref_id::RefId,
+    LoadOptions
+};
+
+const VERSES: &str = r#"{"id":"Gen.1.1","words":[]}
+{"id":"Gen.1.2","words":[]}
+{"id":"Gen.1.4","words":[]}
+{"id":"Gen.1.5","words":[]}
+{"id":"Gen.1.6","words":
+{"id":"Gen.1.7","words":[]}
+{"id":"Gen.3.1","words":[]}
+{"id":"Gen.3.2","words":[]}
+{"id":"Exod.1.1","words":[]}
+{"id":"Gen.4.1","words":[]}
+"#;
+
+fn books() -> HashMap<String, String>
+{
+    HashMap::from([("Gen".to_owned(), "Genesis".to_owned()), ("Exod".to_owned(), "Exodus".to_owned())])
+}
+
+fn load(options: &LoadOptions) -> Vec<LoadError>
+{
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("kjv.jsonl").display().to_string();
+    fs::write(&path, VERSES).unwrap();
+
+    BibleSource::from_file_with(&path, &books(), options).unwrap_err()
+}
+
+/// The line of each error, and the ordering error if it is one
+fn summary(errors: &[LoadError]) -> Vec<(Option<usize>, Option<&OrderingError>)>
+{
+    errors.iter().map(|e| match e {
+        LoadError::Ordering { path: _, line, error } => (Some(*line), Some(error)),
+        e => (e.line(), None),
+    }).collect()
+}
+
+#[test]
+fn accumulates_every_ordering_error_once()
+{
+    let errors = load(&LoadOptions::accumulate());
+
+    // Gen.1.7 follows a line that failed to parse, so the missing Gen.1.6 is not reported again
+    assert_eq!(summary(&errors), vec![
+        (Some(5), None),
+        (Some(3), Some(&OrderingError::Verse { id: RefId::from_str("Gen.1.4").unwrap(), expected: 3 })),
+        (Some(7), Some(&OrderingError::Chapter { id: RefId::from_str("Gen.3.1").unwrap(), expected: 2 })),
+        (Some(10), Some(&OrderingError::Book { book: "Gen".to_owned() })),
+    ]);
+}
+
+#[test]
+fn stops_at_the_first_error_or_the_limit()
+{
+    assert_eq!(load(&LoadOptions::default()).len(), 1);
+    assert_eq!(load(&LoadOptions::accumulate().with_error_limit(NonZeroUsize::new(2).unwrap())).len(), 2);
+
+    // the smallest limit still keeps an error, so a broken file can never load
+    assert_eq!(load(&LoadOptions::accumulate().with_error_limit(NonZeroUsize::MIN)).len(), 1);
+}
+
+#[test]
+fn verses_from_memory_are_checked_the_same_way()
+{
+    let verse = |id: &str| Verse { id: RefId::from_str(id).unwrap(), words: vec![] };
+    let verses = || vec![verse("Gen.1.1"), verse("Gen.1.3"), verse("Gen.2.1")];
+
+    let errors = BibleSource::from_verses("KJV", verses(), &books(), &LoadOptions::accumulate()).unwrap_err();
+    assert_eq!(summary(&errors), vec![(Some(2), Some(&OrderingError::Verse { id: RefId::from_str("Gen.1.3").unwrap(), expected: 2 }))]);
+    assert_eq!(errors[0].path(), "KJV");
+
+    // the builder reports a missing book name along with the ordering error
+    let builder = verses().into_iter().fold(BibleBuilder::new("KJV", "en"), |b, v| b.verse(v));
+    assert_eq!(builder.build().unwrap_err().len(), 2);
+}
@@ -0,0 +1,54 @@
+use std::{fs, path::{Path, PathBuf}};
+
+use biblio_json::{LoadOptions, Package};
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/round_trip");
+
+/// Every file under `root`, relative to it and sorted
+fn files(root: &Path) -> Vec<PathBuf>
+{
+    let mut files = vec![];
+    let mut stack = vec![PathBuf::new()];
+    while let Some(relative) = stack.pop()
+    {
+        for entry in fs::read_dir(root.join(&relative)).unwrap()
+        {
+            let entry = entry.unwrap();
+            let path = relative.join(entry.file_name());
+            if entry.file_type().unwrap().is_dir() { stack.push(path) } else { files.push(path) }
+        }
+    }
+
+    files.sort();
+    files
+}
+
+fn assert_round_trip(package: &Package)
+{
+    let out = tempfile::tempdir().unwrap();
+    package.save(out.path().to_str().unwrap()).unwrap();
+
+    let expected = files(Path::new(FIXTURE));
+    assert_eq!(expected, files(out.path()));
+
+    for file in expected
+    {
+        let expected = fs::read(Path::new(FIXTURE).join(&file)).unwrap();
+        let actual = fs::read(out.path().join(&file)).unwrap();
+        assert!(expected == actual, "{} was not written back byte for byte:\n{}", file.display(), String::from_utf8_lossy(&actual));
+    }
+}
+
+#[test]
+fn saving_writes_every_file_back_byte_for_byte()
+{
+    assert_round_trip(&Package::load(FIXTURE).unwrap());
+}
+
+#[test]
+fn saving_a_lazy_package_writes_every_file_back_byte_for_byte()
+{
+    let package = Package::load_with(FIXTURE, &LoadOptions::lazy()).unwrap();
+    assert_round_trip(&package);
+    assert!(package.modules.iter().all(|m| !m.is_loaded()));
+}
@@ -0,0 +1,66 @@
+use std::str::FromStr;
+
+use biblio_json::{builder::XRefBuilder, ref_id::RefId, xref_graph::XRefGraph};
+
+fn id(s: &str) -> RefId
+{
+    RefId::from_str(s).unwrap()
+}
+
+fn graph() -> XRefGraph
+{
+    let module = XRefBuilder::new("Creation")
+        .directed(id("Gen.1.1"), [id("John.1.1-John.1.3")])
+        .directed(id("Ps.33.6"), [id("John.1.3")])
+        .directed(id("Heb.11.3"), [id("John.1")])
+        .directed(id("John.1.3"), [id("Col.1.16")])
+        .mutual([id("Col.1.16"), id("Heb.1.2")])
+        .directed(id("Rev.1.1"), [id("Rev.1.2")])
+        .build()
+        .unwrap();
+
+    XRefGraph::from_module(&module)
+}
+
+#[test]
+fn mutual_references_go_both_ways()
+{
+    let graph = graph();
+    assert_eq!(graph.nodes().len(), 10);
+    assert_eq!(graph.edge_count(), 7);
+    assert_eq!(graph.in_degree(&id("Heb.1.2")), 1);
+    assert_eq!(graph.out_degree(&id("Heb.1.2")), 1);
+    assert_eq!(graph.out_degree(&id("Rev.1.2")), 0);
+}
+
+#[test]
+fn links_to_ranges_count_for_the_verses_inside()
+{
+    let graph = graph();
+
+    // John.1.3 is linked directly, through the range and through the chapter
+    assert_eq!(graph.most_referenced(2), vec![(&id("John.1.3"), 3), (&id("John.1.1-John.1.3"), 2)]);
+}
+
+#[test]
+fn chains_pass_through_ranges()
+{
+    let graph = graph();
+
+    let chain = graph.shortest_chain(&id("Gen.1.1"), &id("Heb.1.2")).unwrap();
+    assert_eq!(chain, vec![&id("Gen.1.1"), &id("John.1.1-John.1.3"), &id("John.1.3"), &id("Col.1.16"), &id("Heb.1.2")]);
+
+    assert_eq!(graph.shortest_chain(&id("Col.1.16"), &id("Gen.1.1")), None);
+    assert_eq!(graph.shortest_chain(&id("Gen.1.1"), &id("Rev.1.2")), None);
+}
+
+#[test]
+fn components_follow_links_and_ranges()
+{
+    let graph = graph();
+    let components = graph.connected_components();
+
+    // the chapter John.1 joins the links into it with the links into the verses it covers
+    assert_eq!(components.iter().map(|c| c.len()).collect::<Vec<_>>(), vec![8, 2]);
+    assert_eq!(components[1], vec![&id("Rev.1.1"), &id("Rev.1.2")]);
+}
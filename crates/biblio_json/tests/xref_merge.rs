@@ -0,0 +1,93 @@
+use std::{collections::BTreeSet, str::FromStr};
+
+use biblio_json::{builder::XRefBuilder, modules::xrefs::{XRef, XRefModule, XRefTarget}, ref_id::RefId, xref_merge::MergedXRefs};
+
+fn id(s: &str) -> RefId
+{
+    RefId::from_str(s).unwrap()
+}
+
+fn names(names: &[&str]) -> BTreeSet<String>
+{
+    names.iter().map(|n| n.to_string()).collect()
+}
+
+fn modules() -> (XRefModule, XRefModule)
+{
+    let tsk = XRefBuilder::new("TSK")
+        .directed(id("Gen.1.1"), [id("John.1.1-John.1.3")])
+        .directed(id("Gen.1.1"), [id("Gen.1.1")])
+        .xref(XRef::Directed { source: id("Gen.14.18"), source_text: None, targets: vec![id("Heb.7.1").into()], note: Some("Melchizedek".into()) })
+        .chain("Priesthood", [id("Gen.14.18"), id("Ps.110.4"), id("Heb.7.1")])
+        .build()
+        .unwrap();
+
+    let open_bible = XRefBuilder::new("OpenBible")
+        .directed(id("Gen.1.1"), [XRefTarget { id: id("John.1.1"), votes: Some(5) }])
+        .directed(id("Heb.7.1"), [id("Gen.14.18")])
+        .chain("Priesthood", [id("Gen.14.18"), id("Ps.110.4"), id("Heb.7.1")])
+        .build()
+        .unwrap();
+
+    (tsk, open_bible)
+}
+
+#[test]
+fn covered_links_are_merged_into_the_wider_link()
+{
+    let (tsk, open_bible) = modules();
+    let merged = MergedXRefs::merge(&[&tsk, &open_bible]);
+
+    let link = merged.links.iter().find(|l| l.from == id("Gen.1.1")).unwrap();
+    assert_eq!(link.to, id("John.1.1-John.1.3"));
+    assert!(!link.mutual);
+    assert_eq!(link.votes, Some(5));
+    assert_eq!(link.provenance, names(&["OpenBible", "TSK"]));
+
+    // the link from Gen.1.1 to itself is dropped
+    assert_eq!(merged.links.len(), 2);
+}
+
+#[test]
+fn links_given_both_ways_become_mutual()
+{
+    let (tsk, open_bible) = modules();
+    let merged = MergedXRefs::merge(&[&tsk, &open_bible]);
+
+    let link = merged.links.iter().find(|l| l.from == id("Gen.14.18")).unwrap();
+    assert_eq!(link.to, id("Heb.7.1"));
+    assert!(link.mutual);
+    assert_eq!(link.notes, names(&["Melchizedek"]));
+    assert_eq!(link.provenance, names(&["OpenBible", "TSK"]));
+
+    assert_eq!(merged.links_from(&id("Heb.7.1")).len(), 1);
+}
+
+#[test]
+fn chains_are_kept_whole_and_deduplicated()
+{
+    let (tsk, open_bible) = modules();
+    let merged = MergedXRefs::merge(&[&tsk, &open_bible]);
+
+    assert_eq!(merged.chains.len(), 1);
+    assert_eq!(merged.chains[0].topic, "Priesthood");
+    assert_eq!(merged.chains[0].refs.len(), 3);
+    assert_eq!(merged.chains[0].provenance, names(&["OpenBible", "TSK"]));
+}
+
+#[test]
+fn merged_modules_merge_to_the_same_links()
+{
+    let (tsk, open_bible) = modules();
+    let merged = MergedXRefs::merge(&[&tsk, &open_bible]);
+    let module = merged.to_module(tsk.config());
+
+    assert_eq!(module.refs.len(), 3);
+    assert!(matches!(&module.refs[0], XRef::Directed { targets, .. } if targets[0].votes == Some(5)));
+    assert!(matches!(&module.refs[1], XRef::Mutual { note: Some(note), .. } if note == "Melchizedek"));
+
+    let again = MergedXRefs::merge(&[&module]);
+    let pairs = |m: &MergedXRefs| m.links.iter().map(|l| (l.from.clone(), l.to.clone(), l.mutual)).collect::<Vec<_>>();
+    assert_eq!(pairs(&again), pairs(&merged));
+    assert_eq!(again.chains.len(), 1);
+}
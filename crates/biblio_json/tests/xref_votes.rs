@@ -0,0 +1,45 @@
+use std::{fs, str::FromStr};
+
+use biblio_json::{modules::xrefs::{XRefModule, XRefTarget}, ref_id::RefId};
+
+const XREFS: &str = r#"{"type":"directed","source":"Isa.53.5","targets":[{"id":"1Pet.2.24","votes":40},"Rom.4.25",{"id":"Matt.8.17","votes":-3}]}
+{"type":"directed","source":"Isa.53.5","targets":[{"id":"Heb.9.28","votes":52}]}
+"#;
+
+fn id(s: &str) -> RefId
+{
+    RefId::from_str(s).unwrap()
+}
+
+fn ids(targets: Vec<&XRefTarget>) -> Vec<String>
+{
+    targets.iter().map(|t| t.id.to_string()).collect()
+}
+
+#[test]
+fn targets_are_sorted_and_filtered_by_votes()
+{
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("open.toml"), "name = \"Open\"\n").unwrap();
+    fs::write(dir.path().join("open.jsonl"), XREFS).unwrap();
+    let module = XRefModule::load(dir.path().to_str().unwrap(), "open").unwrap();
+
+    // targets without votes come last
+    assert_eq!(ids(module.weighted_targets(&id("Isa.53.5"), None)), vec!["Heb.9.28", "1Pet.2.24", "Matt.8.17", "Rom.4.25"]);
+    assert_eq!(ids(module.weighted_targets(&id("Isa.53.5"), Some(41))), vec!["Heb.9.28"]);
+    assert_eq!(ids(module.weighted_targets(&id("Isa.53.5"), Some(-5))), vec!["Heb.9.28", "1Pet.2.24", "Matt.8.17"]);
+    assert!(module.weighted_targets(&id("Isa.53.6"), None).is_empty());
+}
+
+#[test]
+fn votes_are_kept_when_saving()
+{
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().to_str().unwrap();
+    fs::write(dir.path().join("open.toml"), "name = \"Open\"\n").unwrap();
+    fs::write(dir.path().join("open.jsonl"), XREFS).unwrap();
+
+    let saved = tempfile::tempdir().unwrap();
+    XRefModule::load(path, "open").unwrap().save(saved.path().to_str().unwrap(), "open").unwrap();
+    assert_eq!(fs::read_to_string(saved.path().join("open.jsonl")).unwrap(), XREFS);
+}
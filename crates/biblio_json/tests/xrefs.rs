@@ -0,0 +1,69 @@
+use std::str::FromStr;
+
+use biblio_json::{builder::XRefBuilder, modules::xrefs::XRefModule, ref_id::RefId};
+
+fn id(s: &str) -> RefId
+{
+    RefId::from_str(s).unwrap()
+}
+
+fn module() -> XRefModule
+{
+    XRefBuilder::new("Ranges")
+        .directed(id("Gen.1.1"), [id("John.1.1-John.1.5")])
+        .directed(id("Gen.2.1"), [id("John.1.50-John.2.3")])
+        .directed(id("Ps.23"), [id("John.10.11")])
+        .directed(id("Gen.50.1-Exod.1.5"), [id("Acts.7.15")])
+        .directed(id("Judg.21.1-1Sam.1.2"), [id("Matt.1.5")])
+        .mutual([id("John.1.1-John.1.5"), id("John.1.3")])
+        .build()
+        .unwrap()
+}
+
+fn sources(module: &XRefModule, verse: &str) -> Vec<String>
+{
+    module.sources_pointing_to(&id(verse)).iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn ranges_within_a_book_cover_their_verses()
+{
+    let module = module();
+
+    // both ends of the mutual reference contain John.1.3, so neither points to it
+    assert_eq!(sources(&module, "John.1.3"), vec!["Gen.1.1"]);
+    assert!(sources(&module, "John.1.6").is_empty());
+
+    // across chapters, and whole chapters
+    assert_eq!(sources(&module, "John.1.51"), vec!["Gen.2.1"]);
+    assert_eq!(sources(&module, "John.2.3"), vec!["Gen.2.1"]);
+    assert!(sources(&module, "John.2.4").is_empty());
+    assert_eq!(module.refs_from(&id("Ps.23.4")).len(), 1);
+}
+
+#[test]
+fn ranges_across_books_cover_every_book_between()
+{
+    let module = module();
+
+    for verse in ["Gen.50.20", "Exod.1.3"]
+    {
+        assert_eq!(module.refs_from(&id(verse)).len(), 1, "{}", verse);
+    }
+    assert!(module.refs_from(&id("Gen.49.33")).is_empty());
+    assert!(module.refs_from(&id("Exod.1.6")).is_empty());
+
+    // Ruth is between Judges and 1 Samuel
+    assert_eq!(module.refs_from(&id("Ruth.2.3")).len(), 1);
+    assert!(module.refs_from(&id("1Sam.1.3")).is_empty());
+}
+
+#[test]
+fn references_are_listed_once()
+{
+    let module = module();
+
+    // the mutual reference covers John.1.3 twice, and is both a source and a target
+    assert_eq!(module.refs_touching(&id("John.1.3")).len(), 2);
+    assert_eq!(module.refs_to(&id("John.1.3")).len(), 2);
+}
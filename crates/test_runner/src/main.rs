@@ -1,6 +1,6 @@
 use std::{fs, path::Path, str::FromStr, time::SystemTime};
 
-use biblio_json::{modules::{xrefs::XRef, Module}, ref_id::RefId, Package, PackageValidationError};
+use biblio_json::{diagnostics::{Diagnostic, DiagnosticRenderer}, modules::{xrefs::XRef, Module}, ref_id::RefId, Package, PackageValidationError};
 use itertools::Itertools;
 
 fn main()
@@ -10,14 +10,26 @@ fn main()
             println!("Package loaded!");
             ok
         },
-        Err(e) => return println!("Package loaded with errors:\n{}\n", e.iter().join("\n"))
+        Err(e) => {
+            let diagnostics = e.iter().map(Diagnostic::from).collect_vec();
+            return println!("Package loaded with errors:\n{}", DiagnosticRenderer::for_terminal(&std::io::stdout()).render_all(&diagnostics));
+        }
     };
     
+    let lints = package.lint();
+    if !lints.findings.is_empty()
+    {
+        let diagnostics = lints.findings.iter().map(Diagnostic::from).collect_vec();
+        println!("{}", DiagnosticRenderer::for_terminal(&std::io::stdout()).render_all(&diagnostics));
+    }
+
     if let Err(errors) = package.validate()
     {
         let msg = errors.into_iter()
         .filter_map(|e| match e {
-            PackageValidationError::InvalidRefId { id, bible_name: _, xref_name: _, line } => Some((line, id)),
+            PackageValidationError::InvalidRefId { id, bible_name: _, xref_name: _, path: _, line } => Some((line, id)),
+            PackageValidationError::InvalidDictRefId { id, bible_name: _, dict_name: _, path: _, term: _, line } => Some((line, id)),
+            _ => None,
         })
         .unique_by(|(_, id)| id.clone())
         .enumerate()
@@ -31,4 +43,3 @@ fn main()
         println!("Validation passed!");
     }
 }
-